@@ -0,0 +1,72 @@
+/// The body of the `## [version]` section of a Keep a Changelog style `content`, up to
+/// the next `## ` heading (or the link definitions at the end of the file), with
+/// surrounding blank lines trimmed. `None` when there is no such section or it is empty.
+pub fn section(content: &str, version: &str) -> Option<String> {
+    let mut lines = content.lines();
+    lines.find(|line| is_version_heading(line, version))?;
+    let body: Vec<&str> = lines
+        .take_while(|line| !line.starts_with("## ") && !is_link_definition(line))
+        .collect();
+    let body = body.join("\n");
+    let body = body.trim_matches('\n').trim_end();
+    (!body.is_empty()).then(|| body.to_string())
+}
+
+/// Whether `line` is a `## [X.Y.Z]` heading (optionally `[vX.Y.Z]`) for `version`, with
+/// or without a date after it.
+fn is_version_heading(line: &str, version: &str) -> bool {
+    let Some(heading) = line.strip_prefix("## ") else {
+        return false;
+    };
+    let Some(rest) = heading.trim_start().strip_prefix('[') else {
+        return false;
+    };
+    rest.split_once(']')
+        .is_some_and(|(name, _)| name.strip_prefix('v').unwrap_or(name) == version)
+}
+
+/// Whether `line` is a Markdown link definition such as `[0.2.0]: https://...`.
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && line.contains("]: ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog
+
+## [Unreleased]
+
+- Work in progress
+
+## [0.2.0] - 2024-05-01
+
+### Added
+
+- Parallel loading
+
+### Fixed
+- Tags with a prefix
+
+## [v0.1.0]
+- First release
+
+[0.2.0]: https://example.com/compare/v0.1.0...v0.2.0
+";
+
+    #[test]
+    fn test_section() {
+        assert_eq!(
+            section(CHANGELOG, "0.2.0").as_deref(),
+            Some("### Added\n\n- Parallel loading\n\n### Fixed\n- Tags with a prefix")
+        );
+        assert_eq!(
+            section(CHANGELOG, "0.1.0").as_deref(),
+            Some("- First release")
+        );
+        assert_eq!(section(CHANGELOG, "0.2"), None);
+        assert_eq!(section(CHANGELOG, "0.3.0"), None);
+        assert_eq!(section("## [1.0.0]\n\n## [0.9.0]\n- x\n", "1.0.0"), None);
+    }
+}
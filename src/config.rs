@@ -1,24 +1,454 @@
+use crate::scheme::VersionScheme;
 use anyhow::{Context, Result};
+use glob::glob;
 use serde::Deserialize;
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
 
 #[derive(Debug, Deserialize)]
 pub struct MetaConfig {
     pub workspace: WorkspaceConfig,
+    /// The file as written, so edits keep its formatting and comments.
+    #[serde(skip)]
+    doc: DocumentMut,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WorkspaceConfig {
+    #[serde(default)]
     pub members: Vec<String>,
+    /// Cargo manifest whose `[workspace] members` are added to `members`.
+    pub inherit: Option<String>,
+    /// Remote used by push/pull/fetch and remote deletions. Defaults to `origin`.
+    pub remote: Option<String>,
+    /// Prefix of version tags. Defaults to `v`.
+    pub tag_prefix: Option<String>,
+    /// Make `meta pull` rebase by default.
+    #[serde(default)]
+    pub pull_rebase: bool,
+    /// How `meta bump` validates and increments versions.
+    #[serde(default)]
+    pub version_scheme: VersionScheme,
+}
+
+/// Why a member listed in Meta.toml can't be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingMember {
+    NoDirectory,
+    NoManifest,
+}
+
+impl fmt::Display for MissingMember {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NoDirectory => "directory not found",
+            Self::NoManifest => "no Cargo.toml",
+        })
+    }
 }
 
 impl MetaConfig {
-    pub fn load() -> Result<Self> {
-        let content = fs::read_to_string("Meta.toml").context(
-            "Failed to read Meta.toml. Make sure you are in the root of the meta-workspace.",
+    /// A fresh configuration listing `members`.
+    pub fn new(members: Vec<String>) -> Self {
+        let mut doc = DocumentMut::new();
+        doc["workspace"] = toml_edit::table();
+        let mut config = Self {
+            workspace: WorkspaceConfig {
+                members: Vec::new(),
+                inherit: None,
+                remote: None,
+                tag_prefix: None,
+                pull_rebase: false,
+                version_scheme: VersionScheme::default(),
+            },
+            doc,
+        };
+        config.set_members(members);
+        config
+    }
+
+    /// Replace `[workspace] members`, keeping the rest of the file untouched. Members
+    /// that were already listed keep their comments.
+    pub fn set_members(&mut self, members: Vec<String>) {
+        let item = &mut self.doc["workspace"]["members"];
+        let mut array = toml_edit::Array::new();
+        for member in &members {
+            let existing = item
+                .as_array()
+                .and_then(|old| old.iter().find(|v| v.as_str() == Some(member)));
+            match existing {
+                Some(value) => array.push_formatted(value.clone()),
+                None => array.push(member),
+            }
+        }
+        if let Some(old) = item.as_array() {
+            array.decor_mut().clone_from(old.decor());
+            array.set_trailing(old.trailing().clone());
+            array.set_trailing_comma(old.trailing_comma());
+        }
+        *item = toml_edit::value(array);
+        self.workspace.members = members;
+    }
+
+    /// The configuration as it would be written to Meta.toml.
+    pub fn to_toml(&self) -> String {
+        self.doc.to_string()
+    }
+
+    /// Write the configuration to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_toml()).with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// Whether the file sets `[workspace] key`, even if to its default value.
+    pub fn sets_workspace_key(&self, key: &str) -> bool {
+        self.doc
+            .get("workspace")
+            .and_then(|workspace| workspace.get(key))
+            .is_some()
+    }
+
+    /// Read Meta.toml from the current directory without checking its members.
+    pub fn read() -> Result<Self> {
+        Self::read_from(Path::new("."))
+    }
+
+    /// Read `root/Meta.toml` without checking its members.
+    pub fn read_from(root: &Path) -> Result<Self> {
+        let content = fs::read_to_string(root.join("Meta.toml"))
+            .with_context(|| format!("Failed to read Meta.toml in {:?}", root))?;
+        Self::parse(&content, root)
+    }
+
+    /// Parse Meta.toml content for a workspace rooted at `root`, expanding glob members
+    /// and resolving `inherit`.
+    fn parse(content: &str, root: &Path) -> Result<Self> {
+        let mut config: MetaConfig = toml_edit::de::from_str(content).map_err(|e| {
+            anyhow::anyhow!("Failed to parse Meta.toml: {}", e.to_string().trim_end())
+        })?;
+        config.doc = content.parse().context("Failed to parse Meta.toml")?;
+
+        let mut members = Vec::new();
+        for member in &config.workspace.members {
+            // A pattern that matches nothing is kept so it gets reported as missing
+            let mut expanded = if is_glob(member) {
+                expand_member_glob(root, member)?
+            } else {
+                Vec::new()
+            };
+            if expanded.is_empty() {
+                expanded.push(member.clone());
+            }
+            for member in expanded {
+                if !members.contains(&member) {
+                    members.push(member);
+                }
+            }
+        }
+        config.workspace.members = members;
+
+        if let Some(manifest) = &config.workspace.inherit {
+            for member in cargo_workspace_members(root, Path::new(manifest))? {
+                if !config.workspace.members.contains(&member) {
+                    config.workspace.members.push(member);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Members (relative to `root`) that are missing a directory or a Cargo.toml,
+    /// each with the reason.
+    pub fn missing_members(&self, root: &Path) -> Vec<(&str, MissingMember)> {
+        self.workspace
+            .members
+            .iter()
+            .filter_map(|member| {
+                let dir = root.join(member);
+                let reason = if !dir.is_dir() {
+                    MissingMember::NoDirectory
+                } else if !dir.join("Cargo.toml").is_file() {
+                    MissingMember::NoManifest
+                } else {
+                    return None;
+                };
+                Some((member.as_str(), reason))
+            })
+            .collect()
+    }
+
+    pub fn validate_members(&self, root: &Path) -> Result<()> {
+        let missing: Vec<String> = self
+            .missing_members(root)
+            .into_iter()
+            .map(|(member, reason)| format!("{} ({})", member, reason))
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Meta.toml lists {} member(s) that don't exist:\n  {}",
+                missing.len(),
+                missing.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The closest directory at or above `start` that holds a Meta.toml.
+pub fn find_workspace_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join("Meta.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+fn is_glob(member: &str) -> bool {
+    member.contains(['*', '?', '['])
+}
+
+/// Crate directories (holding a Cargo.toml) matching `pattern`, as sorted paths
+/// relative to `root`.
+pub fn expand_member_glob(root: &Path, pattern: &str) -> Result<Vec<String>> {
+    let full_pattern = root.join(pattern);
+    let mut members = Vec::new();
+    for path in glob(&full_pattern.to_string_lossy())
+        .with_context(|| format!("Invalid member pattern '{}'", pattern))?
+    {
+        let path = path?;
+        if !path.join("Cargo.toml").is_file() {
+            continue;
+        }
+        let rel = path.strip_prefix(root).unwrap_or(&path);
+        members.push(rel.to_string_lossy().replace('\\', "/"));
+    }
+    members.sort();
+    Ok(members)
+}
+
+/// Members of the Cargo workspace declared in `root/manifest`, with globs expanded and
+/// `exclude` applied, as paths relative to `root`.
+fn cargo_workspace_members(root: &Path, manifest: &Path) -> Result<Vec<String>> {
+    let manifest_path = root.join(manifest);
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read inherited manifest {:?}", manifest_path))?;
+    let doc = content.parse::<DocumentMut>().map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to parse inherited manifest {:?}: {}",
+            manifest_path,
+            e.to_string().trim_end()
+        )
+    })?;
+
+    let workspace = doc
+        .get("workspace")
+        .with_context(|| format!("{:?} has no [workspace] section", manifest_path))?;
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|m| m.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let manifest_dir = manifest_path.parent().unwrap_or(root);
+    let excluded: Vec<PathBuf> = patterns("exclude")
+        .iter()
+        .map(|e| manifest_dir.join(e))
+        .collect();
+
+    let mut members = Vec::new();
+    for pattern in patterns("members") {
+        let pattern = manifest_dir.join(&pattern);
+        for path in glob(&pattern.to_string_lossy())? {
+            let path = path?;
+            if excluded.iter().any(|e| path.starts_with(e)) || !path.join("Cargo.toml").is_file() {
+                continue;
+            }
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            let member = rel.to_string_lossy().replace('\\', "/");
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_members() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        fs::create_dir(root.join("present"))?;
+        fs::write(
+            root.join("present/Cargo.toml"),
+            "[package]\nname = \"present\"\n",
         )?;
+        fs::create_dir(root.join("empty"))?;
+
         let config: MetaConfig =
-            toml_edit::de::from_str(&content).context("Failed to parse Meta.toml")?;
-        Ok(config)
+            toml_edit::de::from_str("[workspace]\nmembers = [\"present\", \"empty\", \"gone\"]\n")?;
+        assert_eq!(
+            config.missing_members(root),
+            vec![
+                ("empty", MissingMember::NoManifest),
+                ("gone", MissingMember::NoDirectory)
+            ]
+        );
+
+        let err = config.validate_members(root).unwrap_err().to_string();
+        assert!(err.contains("2 member(s)"), "{}", err);
+        assert!(err.contains("gone (directory not found)"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_points_at_line() {
+        let err = MetaConfig::parse("[workspace]\nmembers = \"a\"\n", Path::new("."))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("Failed to parse Meta.toml: TOML parse error at line 2, column 11"),
+            "{}",
+            err
+        );
+        assert!(err.contains("expected a sequence"), "{}", err);
+    }
+
+    #[test]
+    fn test_save_keeps_comments() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("Meta.toml");
+        let content = r#"# Release train for the networking crates
+[workspace]
+# Keep sorted
+members = [
+    "crate-a",
+    "crate-b", # pinned until 0.4
+]
+remote = "upstream" # fork
+"#;
+        let mut config = MetaConfig::parse(content, temp_dir.path())?;
+        config.save(&path)?;
+        assert_eq!(fs::read_to_string(&path)?, content);
+
+        config.set_members(vec!["crate-a".into(), "crate-b".into(), "crate-c".into()]);
+        config.save(&path)?;
+        let saved = fs::read_to_string(&path)?;
+        assert!(
+            saved.starts_with(
+                "# Release train for the networking crates\n[workspace]\n# Keep sorted\nmembers = ["
+            ),
+            "{}",
+            saved
+        );
+        assert!(
+            saved.contains("remote = \"upstream\" # fork\n"),
+            "{}",
+            saved
+        );
+
+        let reloaded = MetaConfig::parse(&saved, temp_dir.path())?;
+        assert_eq!(
+            reloaded.workspace.members,
+            ["crate-a", "crate-b", "crate-c"]
+        );
+        assert_eq!(reloaded.workspace.remote.as_deref(), Some("upstream"));
+
+        let fresh = MetaConfig::new(vec!["a".into(), "b".into()]);
+        assert_eq!(
+            fresh.doc.to_string(),
+            "[workspace]\nmembers = [\"a\", \"b\"]\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_workspace_root() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().join("meta");
+        fs::create_dir_all(root.join("repo/crates/a"))?;
+        fs::write(root.join("Meta.toml"), "[workspace]\n")?;
+
+        assert_eq!(find_workspace_root(&root), Some(root.clone()));
+        assert_eq!(
+            find_workspace_root(&root.join("repo/crates/a")),
+            Some(root.clone())
+        );
+        assert_eq!(find_workspace_root(temp_dir.path()), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inherit_cargo_workspace_members() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        for dir in ["crates/a", "crates/b", "crates/skip", "tools/x", "extra"] {
+            fs::create_dir_all(root.join(dir))?;
+            fs::write(root.join(dir).join("Cargo.toml"), "[package]\n")?;
+        }
+        fs::create_dir_all(root.join("crates/not-a-crate"))?;
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\", \"tools/x\"]\nexclude = [\"crates/skip\"]\n",
+        )?;
+
+        let config = MetaConfig::parse(
+            "[workspace]\ninherit = \"Cargo.toml\"\nmembers = [\"extra\", \"tools/x\"]\n",
+            root,
+        )?;
+        assert_eq!(
+            config.workspace.members,
+            vec!["extra", "tools/x", "crates/a", "crates/b"]
+        );
+
+        let config = MetaConfig::parse("[workspace]\ninherit = \"Cargo.toml\"\n", root)?;
+        assert_eq!(
+            config.workspace.members,
+            vec!["crates/a", "crates/b", "tools/x"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob_members() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        for dir in ["crates/b", "crates/a", "tools/x"] {
+            fs::create_dir_all(root.join(dir))?;
+            fs::write(root.join(dir).join("Cargo.toml"), "[package]\n")?;
+        }
+        fs::create_dir_all(root.join("crates/not-a-crate"))?;
+
+        let config = MetaConfig::parse(
+            "[workspace]\nmembers = [\"crates/*\", \"tools/x\", \"crates/a\", \"services/*\"]\n",
+            root,
+        )?;
+        assert_eq!(
+            config.workspace.members,
+            vec!["crates/a", "crates/b", "tools/x", "services/*"]
+        );
+        assert_eq!(
+            config.missing_members(root),
+            vec![("services/*", MissingMember::NoDirectory)]
+        );
+
+        Ok(())
     }
 }
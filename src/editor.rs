@@ -1,81 +1,479 @@
 use anyhow::{Context, Result};
 use semver::Version;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
-use toml_edit::{DocumentMut, Value, value};
+use toml_edit::{DocumentMut, Item, Key, TableLike, Value, value};
+
+/// Top-level tables that can declare dependencies.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Rust editions accepted by `set_edition`.
+pub const EDITIONS: [&str; 4] = ["2015", "2018", "2021", "2024"];
+
+/// How a dependency is declared.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// From a registry: `dep = "1.0"` or `dep = { version = "1.0" }`.
+    Registry { version: String },
+    /// A local path, possibly with the version used when publishing.
+    Path {
+        path: String,
+        version: Option<String>,
+    },
+    /// A git repository, pinned by `reference`, possibly with a version.
+    Git {
+        url: String,
+        reference: GitReference,
+        version: Option<String>,
+    },
+    /// Inherited from `[workspace.dependencies]` (`dep.workspace = true`).
+    Workspace,
+}
+
+/// Which commit of a git dependency is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GitReference {
+    Tag(String),
+    Branch(String),
+    Rev(String),
+    /// No tag, branch or rev: the repository's default branch.
+    DefaultBranch,
+}
+
+impl DependencyKind {
+    /// Classify a dependency entry; `None` for entries that are none of the above
+    /// (e.g. a table with neither a version nor a source).
+    fn from_item(item: &Item) -> Option<Self> {
+        if let Some(version) = item.as_str() {
+            return Some(Self::Registry {
+                version: version.to_string(),
+            });
+        }
+        if is_workspace_inherited(item) {
+            return Some(Self::Workspace);
+        }
+
+        let table = item.as_table_like()?;
+        let field = |key: &str| table.get(key).and_then(|v| v.as_str()).map(String::from);
+        let version = field("version");
+        if let Some(url) = field("git") {
+            let reference = if let Some(tag) = field("tag") {
+                GitReference::Tag(tag)
+            } else if let Some(branch) = field("branch") {
+                GitReference::Branch(branch)
+            } else if let Some(rev) = field("rev") {
+                GitReference::Rev(rev)
+            } else {
+                GitReference::DefaultBranch
+            };
+            return Some(Self::Git {
+                url,
+                reference,
+                version,
+            });
+        }
+        if let Some(path) = field("path") {
+            return Some(Self::Path { path, version });
+        }
+        version.map(|version| Self::Registry { version })
+    }
+
+    /// The version requirement, or the git tag when no version is given.
+    pub fn pin(&self) -> Option<&str> {
+        match self {
+            Self::Registry { version } => Some(version),
+            Self::Path { version, .. } => version.as_deref(),
+            Self::Git {
+                version, reference, ..
+            } => version.as_deref().or(match reference {
+                GitReference::Tag(tag) => Some(tag.as_str()),
+                _ => None,
+            }),
+            Self::Workspace => None,
+        }
+    }
+}
+
+/// A `[workspace.package]` field to set in the Cargo workspace root on behalf of a
+/// member that inherits it. Applied by the caller so the root is saved with the members.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootEdit {
+    /// Directory of the Cargo workspace root.
+    pub root: PathBuf,
+    pub key: &'static str,
+    pub value: String,
+}
 
 pub struct CrateEditor {
-    path: PathBuf,
+    /// Crate directory, or `None` for manifests parsed from a string.
+    path: Option<PathBuf>,
     doc: DocumentMut,
 }
 
 impl CrateEditor {
+    /// Load `<path>/Cargo.toml`.
     pub fn new(path: &Path) -> Result<Self> {
         let manifest_path = path.join("Cargo.toml");
         let content = fs::read_to_string(&manifest_path)
             .with_context(|| format!("Failed to read Cargo.toml at {:?}", manifest_path))?;
-        let doc = content
-            .parse::<DocumentMut>()
-            .with_context(|| format!("Failed to parse Cargo.toml at {:?}", manifest_path))?;
+        // Inline toml_edit's message so the line, column and snippet survive in
+        // one-line error reports
+        let mut editor = Self::from_content(&content).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to parse Cargo.toml at {:?}: {}",
+                manifest_path,
+                e.to_string().trim_end()
+            )
+        })?;
+        editor.path = Some(path.to_path_buf());
+        Ok(editor)
+    }
 
+    /// Parse a manifest held in memory. Such an editor can't be saved and can't follow
+    /// workspace inheritance to other manifests.
+    pub fn from_content(content: &str) -> Result<Self> {
         Ok(Self {
-            path: path.to_path_buf(),
-            doc,
+            path: None,
+            doc: content.parse::<DocumentMut>()?,
         })
     }
 
-    pub fn bump_version(&mut self, new_version: &Version) -> Result<()> {
-        self.doc["package"]["version"] = value(new_version.to_string());
-        Ok(())
-    }
-
-    pub fn update_dependencies(&mut self, members: &[String], new_version: &Version) -> Result<()> {
-        // Iterate over table types that contain dependencies
-        let tables = ["dependencies", "dev-dependencies", "build-dependencies"];
-
-        for table_name in tables {
-            if let Some(table) = self.doc.get_mut(table_name).and_then(|t| t.as_table_mut()) {
-                for (dep_name, dep_item) in table.iter_mut() {
-                    // Check if the dependency is one of our workspace members
-                    // We need to implement a way to map member paths to package names potentially,
-                    // or for now assume dependency name matches package name or directory name?
-                    // Implementation plan assumption: "Identify dependencies that match other packages in the workspace"
-                    // Correct approach: We need to know the PACKAGE NAME of each member.
-                    // But for this pass, we might need that mapping passed in.
-                    // For now, let's assume we have a set of member names.
-                    if members.contains(&dep_name.to_string()) {
-                        if let Some(item) = dep_item.as_inline_table_mut() {
-                            if item.contains_key("version") {
-                                item.insert("version", Value::from(new_version.to_string()));
-                            }
-
-                            // Check for branch and replace with tag
-                            if item.contains_key("branch") {
-                                item.remove("branch");
-                                item.insert("tag", Value::from(format!("v{}", new_version)));
-                            } else if let Some(tag_item) = item.get_mut("tag") {
-                                if let Some(tag_str) = tag_item.as_str() {
-                                    let has_v = tag_str.starts_with('v');
-                                    let new_tag = if has_v {
-                                        format!("v{}", new_version)
-                                    } else {
-                                        new_version.to_string()
-                                    };
-                                    *tag_item = Value::from(new_tag);
-                                }
-                            }
-                        } else if dep_item.is_value() {
-                            // Handle simple "dep = '1.0'"
-                            *dep_item = value(new_version.to_string());
-                        }
-                        // TODO: Handle struct-like tables? e.g. [dependencies.foo]
-                    }
+    /// Set `[package].version`. When the crate inherits it (`version.workspace = true`),
+    /// `[workspace.package].version` is set instead: here if the crate is its own
+    /// workspace root, otherwise the edit is returned for the caller to apply to the root.
+    pub fn bump_version(&mut self, new_version: &Version) -> Result<Option<RootEdit>> {
+        self.set_inheritable("version", &new_version.to_string())
+    }
+
+    /// Whether the crate inherits its version (`version.workspace = true`).
+    pub fn inherits_version(&self) -> bool {
+        self.doc
+            .get("package")
+            .and_then(|p| p.get("version"))
+            .is_some_and(is_workspace_inherited)
+    }
+
+    /// The manifest holding this crate's version: the Cargo workspace root's when the
+    /// version is inherited from another manifest, otherwise its own.
+    pub fn version_manifest(&self) -> Result<PathBuf> {
+        let own = self.dir()?.join("Cargo.toml");
+        if !self.inherits_version() || self.doc.contains_key("workspace") {
+            return Ok(own);
+        }
+        Ok(self
+            .workspace_root()?
+            .map_or(own, |root| root.join("Cargo.toml")))
+    }
+
+    /// Set `[package].edition`, or `[workspace.package].edition` when it is inherited,
+    /// as in `bump_version`.
+    pub fn set_edition(&mut self, edition: &str) -> Result<Option<RootEdit>> {
+        if !EDITIONS.contains(&edition) {
+            anyhow::bail!(
+                "Unknown edition '{}' (expected one of {})",
+                edition,
+                EDITIONS.join(", ")
+            );
+        }
+        self.set_inheritable("edition", edition)
+    }
+
+    /// Set `[package].<key>`. When the crate inherits it, set `[workspace.package].<key>`
+    /// if the crate is its own workspace root, or return the edit for the root.
+    fn set_inheritable(&mut self, key: &'static str, value: &str) -> Result<Option<RootEdit>> {
+        let inherited = self
+            .doc
+            .get("package")
+            .and_then(|p| p.get(key))
+            .is_some_and(is_workspace_inherited);
+        if !inherited {
+            set_str_value(&mut self.doc["package"][key], value);
+            return Ok(None);
+        }
+
+        // The crate is its own workspace root
+        if self.doc.contains_key("workspace") {
+            set_str_value(&mut self.doc["workspace"]["package"][key], value);
+            return Ok(None);
+        }
+
+        let dir = self.dir()?;
+        let root = self.workspace_root()?.with_context(|| {
+            format!(
+                "{:?} inherits its {} but no Cargo workspace root was found",
+                dir, key
+            )
+        })?;
+        Ok(Some(RootEdit {
+            root,
+            key,
+            value: value.to_string(),
+        }))
+    }
+
+    /// Apply `edit` to this editor, the Cargo workspace root it targets.
+    pub fn apply_root_edit(&mut self, edit: &RootEdit) {
+        set_str_value(&mut self.doc["workspace"]["package"][edit.key], &edit.value);
+    }
+
+    /// Set an arbitrary `[package]` field. `version` is refused: bumps must go through
+    /// `bump_version` so dependents are updated too.
+    pub fn set_package_field(&mut self, key: &str, new_value: Value) -> Result<()> {
+        if key == "version" {
+            anyhow::bail!("Refusing to set 'version' directly; use `meta bump` instead");
+        }
+
+        let slot = &mut self.doc["package"][key];
+        match slot.as_value_mut() {
+            Some(current) => {
+                let decor = current.decor().clone();
+                *current = new_value;
+                *current.decor_mut() = decor;
+            }
+            None => *slot = Item::Value(new_value),
+        }
+        Ok(())
+    }
+
+    /// Set `[package].name`.
+    pub fn rename_package(&mut self, new_name: &str) -> Result<()> {
+        let valid = !new_name.is_empty()
+            && new_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            anyhow::bail!("Invalid package name '{}'", new_name);
+        }
+        set_str_value(&mut self.doc["package"]["name"], new_name);
+        Ok(())
+    }
+
+    /// Make every dependency on package `old` refer to `new`. Entries keyed by the
+    /// package name get the new key (keeping their position, version, path and git
+    /// coordinates); renamed entries (`alias = { package = "old" }`) keep their alias
+    /// and get a new `package`.
+    ///
+    /// Returns whether anything changed.
+    pub fn rename_dependency(&mut self, old: &str, new: &str) -> Result<bool> {
+        let mut changed = false;
+        let mut paths = self.dependency_table_paths();
+        paths.extend(self.patch_table_paths());
+        for path in paths {
+            let Some(table) = path
+                .iter()
+                .try_fold(self.doc.as_item_mut(), |item, key| item.get_mut(key))
+                .and_then(|t| t.as_table_like_mut())
+            else {
+                continue;
+            };
+
+            let mut rename_key = false;
+            for (key, dep_item) in table.iter_mut() {
+                let Some(package) = dep_item
+                    .as_table_like_mut()
+                    .and_then(|t| t.get_mut("package"))
+                else {
+                    rename_key |= key.get() == old;
+                    continue;
+                };
+                if package.as_str() == Some(old) {
+                    set_str_value(package, new);
+                    changed = true;
+                }
+            }
+            if rename_key {
+                rename_table_key(table, old, new);
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Remove every dependency on package `name` (including renamed ones and `[patch]`
+    /// entries), along with the features' references to it. Returns whether anything
+    /// was removed.
+    pub fn remove_dependency(&mut self, name: &str) -> bool {
+        let mut removed = HashSet::new();
+        let mut paths = self.dependency_table_paths();
+        paths.extend(self.patch_table_paths());
+        for path in paths {
+            let Some(table) = path
+                .iter()
+                .try_fold(self.doc.as_item_mut(), |item, key| item.get_mut(key))
+                .and_then(|t| t.as_table_like_mut())
+            else {
+                continue;
+            };
+
+            let keys: Vec<String> = table
+                .iter()
+                .filter(|(key, dep_item)| dependency_package_name(key, dep_item) == name)
+                .map(|(key, _)| key.to_string())
+                .collect();
+            for key in keys {
+                table.remove(&key);
+                removed.insert(key);
+            }
+        }
+
+        // `dep`, `dep:dep`, `dep/feature` and `dep?/feature` would dangle
+        if let Some(features) = self
+            .doc
+            .get_mut("features")
+            .and_then(|f| f.as_table_like_mut())
+        {
+            for (_, enables) in features.iter_mut() {
+                if let Some(enables) = enables.as_array_mut() {
+                    enables.retain(|v| {
+                        let Some(v) = v.as_str() else {
+                            return true;
+                        };
+                        let dep = v.strip_prefix("dep:").unwrap_or(v);
+                        let dep = dep.split_once('/').map_or(dep, |(dep, _)| dep);
+                        !removed.contains(dep.trim_end_matches('?'))
+                    });
                 }
             }
         }
+        !removed.is_empty()
+    }
+
+    /// The closest directory above this crate whose manifest has a `[workspace]`.
+    fn workspace_root(&self) -> Result<Option<PathBuf>> {
+        let dir = self.dir()?.canonicalize()?;
+        for parent in dir.ancestors().skip(1) {
+            let manifest = parent.join("Cargo.toml");
+            if manifest.is_file() && CrateEditor::new(parent)?.doc.contains_key("workspace") {
+                return Ok(Some(parent.to_path_buf()));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn update_dependencies(
+        &mut self,
+        members: &[String],
+        new_version: &Version,
+        tag_prefix: &str,
+    ) -> Result<()> {
+        for member in members {
+            self.set_dependency_version(member, new_version, tag_prefix)?;
+        }
         Ok(())
     }
 
+    /// Point every dependency on `dep_name` at `version`, across all dependency tables.
+    /// Registry versions are replaced, git tags are rewritten (keeping `tag_prefix` if
+    /// present) and git branches are pinned to the version tag.
+    ///
+    /// Returns whether any entry was found and changed.
+    pub fn set_dependency_version(
+        &mut self,
+        dep_name: &str,
+        version: &Version,
+        tag_prefix: &str,
+    ) -> Result<bool> {
+        Ok(self.update_dependency_entries(dep_name, |dep_item| {
+            let tag_changed = set_item_tag(dep_item, version, tag_prefix);
+            set_item_version(dep_item, version) | tag_changed
+        }))
+    }
+
+    /// Like `set_dependency_version`, but only for registry dependencies: path, git and
+    /// workspace-inherited entries are left alone with a warning.
+    pub fn set_registry_dependency_version(
+        &mut self,
+        dep_name: &str,
+        version: &Version,
+    ) -> Result<bool> {
+        let crate_name = self.get_package_name().unwrap_or_default();
+        Ok(self.update_dependency_entries(dep_name, |dep_item| {
+            let source = match DependencyKind::from_item(dep_item) {
+                Some(DependencyKind::Registry { .. }) => {
+                    return set_item_version(dep_item, version);
+                }
+                Some(DependencyKind::Path { .. }) => "a path",
+                Some(DependencyKind::Git { .. }) => "a git",
+                Some(DependencyKind::Workspace) => "a workspace-inherited",
+                None => return false,
+            };
+            warn!(
+                "Warning: {} has {} dependency on {}; left unchanged",
+                crate_name, source, dep_name
+            );
+            false
+        }))
+    }
+
+    /// Apply `update` to every entry depending on package `dep_name`, across all
+    /// dependency tables. Returns whether any call reported a change.
+    fn update_dependency_entries<F>(&mut self, dep_name: &str, mut update: F) -> bool
+    where
+        F: FnMut(&mut Item) -> bool,
+    {
+        let mut changed = false;
+        let mut paths = self.dependency_table_paths();
+        paths.extend(self.patch_table_paths());
+        for path in paths {
+            let Some(table) = path
+                .iter()
+                .try_fold(self.doc.as_item_mut(), |item, key| item.get_mut(key))
+                .and_then(|t| t.as_table_like_mut())
+            else {
+                continue;
+            };
+
+            for (key, dep_item) in table.iter_mut() {
+                if dependency_package_name(key.get(), dep_item) == dep_name {
+                    changed |= update(dep_item);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Check the manifest has what version management relies on: a `[package]` with a
+    /// `name` and a semver `version` (or `version.workspace = true`).
+    pub fn validate(&self) -> Result<()> {
+        let Some(package) = self.doc.get("package").and_then(|p| p.as_table_like()) else {
+            anyhow::bail!("missing [package] section");
+        };
+
+        let mut problems = Vec::new();
+        if package.get("name").and_then(|n| n.as_str()).is_none() {
+            problems.push("missing package name".to_string());
+        }
+        match package.get("version") {
+            None => problems.push("missing package version".to_string()),
+            Some(version) if is_workspace_inherited(version) => {}
+            Some(version) => match version.as_str() {
+                Some(v) => {
+                    if let Err(e) = Version::parse(v) {
+                        problems.push(format!("invalid version '{}': {}", v, e));
+                    }
+                }
+                None => problems.push("package version is not a string".to_string()),
+            },
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!("{}", problems.join("; "));
+        }
+        Ok(())
+    }
+
+    /// The crate directory this editor was loaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn dir(&self) -> Result<&Path> {
+        self.path()
+            .context("Manifest was parsed from memory and has no location on disk")
+    }
+
     pub fn get_package_name(&self) -> Option<String> {
         self.doc
             .get("package")
@@ -84,16 +482,459 @@ impl CrateEditor {
             .map(|s| s.to_string())
     }
 
-    pub fn save(&self) -> Result<()> {
-        let manifest_path = self.path.join("Cargo.toml");
-        fs::write(manifest_path, self.doc.to_string())?;
-        Ok(())
+    /// `[package].version`, or `[workspace.package].version` from the Cargo workspace
+    /// root when the version is inherited.
+    pub fn get_version(&self) -> Option<String> {
+        if !self.inherits_version() {
+            return self
+                .doc
+                .get("package")
+                .and_then(|p| p.get("version"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+        }
+
+        if self.doc.contains_key("workspace") {
+            return self.workspace_version();
+        }
+        let root = self.workspace_root().ok()??;
+        CrateEditor::new(&root).ok()?.workspace_version()
+    }
+
+    /// `[workspace.package].version`, the version members inherit from this manifest.
+    pub fn workspace_version(&self) -> Option<String> {
+        self.doc
+            .get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("version"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// The `[features]` table: each feature with the features and dependencies it enables.
+    pub fn get_features(&self) -> BTreeMap<String, Vec<String>> {
+        let Some(features) = self.doc.get("features").and_then(|f| f.as_table_like()) else {
+            return BTreeMap::new();
+        };
+        features
+            .iter()
+            .map(|(name, enables)| {
+                let enables = enables
+                    .as_array()
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (name.to_string(), enables)
+            })
+            .collect()
+    }
+
+    /// Names of the workspace members this crate depends on, across all dependency
+    /// tables and declaration forms. Renamed dependencies resolve to their real package.
+    pub fn get_dependencies(&self, members: &HashSet<String>) -> Vec<String> {
+        member_names(self.dependency_items(), members)
+    }
+
+    /// Like `get_dependencies`, but leaving out `[dev-dependencies]`. Cargo allows cycles
+    /// through dev-dependencies, so these are the edges that decide the build order.
+    pub fn get_build_order_dependencies(&self, members: &HashSet<String>) -> Vec<String> {
+        let items = self.dependency_items_in(
+            self.dependency_table_paths()
+                .into_iter()
+                .filter(|path| path.last().is_some_and(|t| t != "dev-dependencies")),
+        );
+        member_names(items, members)
+    }
+
+    /// Returns `(package, pin)` for every dependency on one of `members`, where `pin` is
+    /// the declared version requirement, or the git tag when no version is given.
+    pub fn get_dependency_pins(&self, members: &HashSet<String>) -> Vec<(String, String)> {
+        let mut pins = Vec::new();
+        for (key, item) in self.dependency_items() {
+            let package = dependency_package_name(key, item);
+            if !members.contains(package) {
+                continue;
+            }
+
+            let kind = DependencyKind::from_item(item);
+            if let Some(pin) = kind.as_ref().and_then(DependencyKind::pin) {
+                pins.push((package.to_string(), pin.to_string()));
+            }
+        }
+        pins
+    }
+
+    /// How the dependency on package `name` is declared, taking the first entry found
+    /// across all dependency tables.
+    pub fn get_dependency_spec(&self, name: &str) -> Option<DependencyKind> {
+        self.dependency_items()
+            .into_iter()
+            .find(|(key, item)| dependency_package_name(key, item) == name)
+            .and_then(|(_, item)| DependencyKind::from_item(item))
+    }
+
+    /// All `(key, item)` entries across the dependency tables.
+    fn dependency_items(&self) -> Vec<(&str, &Item)> {
+        self.dependency_items_in(self.dependency_table_paths())
+    }
+
+    /// The `(key, item)` entries of the dependency tables at `paths`.
+    fn dependency_items_in(
+        &self,
+        paths: impl IntoIterator<Item = Vec<String>>,
+    ) -> Vec<(&str, &Item)> {
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                path.iter()
+                    .try_fold(self.doc.as_item(), |item, key| item.get(key))
+                    .and_then(|t| t.as_table_like())
+            })
+            .flat_map(|table| table.iter())
+            .collect()
+    }
+
+    /// Key paths of every dependency table, including the ones nested under
+    /// `[target.<cfg or triple>]`.
+    fn dependency_table_paths(&self) -> Vec<Vec<String>> {
+        let mut paths: Vec<Vec<String>> = DEPENDENCY_TABLES
+            .iter()
+            .map(|table| vec![table.to_string()])
+            .collect();
+        if let Some(targets) = self.doc.get("target").and_then(|t| t.as_table_like()) {
+            for (spec, _) in targets.iter() {
+                for table in DEPENDENCY_TABLES {
+                    paths.push(vec![
+                        "target".to_string(),
+                        spec.to_string(),
+                        table.to_string(),
+                    ]);
+                }
+            }
+        }
+        paths
+    }
+
+    /// Key paths of the `[patch.<source>]` tables. They override dependencies rather than
+    /// declare them, so they are edited along with dependencies (versions, tags, renames)
+    /// but not listed as dependencies.
+    fn patch_table_paths(&self) -> Vec<Vec<String>> {
+        let Some(patches) = self.doc.get("patch").and_then(|p| p.as_table_like()) else {
+            return Vec::new();
+        };
+        patches
+            .iter()
+            .map(|(source, _)| vec!["patch".to_string(), source.to_string()])
+            .collect()
+    }
+
+    /// The values that differ between `other_content` (the old manifest) and this
+    /// editor's document, by dotted key path. Formatting and comments are ignored.
+    pub fn diff_against(&self, other_content: &str) -> Result<Vec<FieldChange>> {
+        let other = other_content.parse::<DocumentMut>().map_err(|e| {
+            anyhow::anyhow!("Failed to parse Cargo.toml: {}", e.to_string().trim_end())
+        })?;
+        let mut changes = Vec::new();
+        diff_items(
+            "",
+            Some(other.as_item()),
+            Some(self.doc.as_item()),
+            &mut changes,
+        );
+        Ok(changes)
+    }
+
+    /// Like `diff_against`, comparing with the manifest on disk.
+    pub fn diff_against_disk(&self) -> Result<Vec<FieldChange>> {
+        let manifest_path = self.dir()?.join("Cargo.toml");
+        let content = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read Cargo.toml at {:?}", manifest_path))?;
+        self.diff_against(&content)
+    }
+}
+
+/// A value that differs between two versions of a manifest. `old` is `None` for added
+/// keys and `new` is `None` for removed ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// Dotted key path, e.g. `dependencies.serde.version`.
+    pub path: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{}: {} -> {}", self.path, old, new),
+            (None, Some(new)) => write!(f, "+ {} = {}", self.path, new),
+            (Some(old), None) => write!(f, "- {} (was {})", self.path, old),
+            (None, None) => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Collect the differences between `old` and `new` below `path`. Tables (standard or
+/// inline) are compared key by key; anything else, including a table replaced by a
+/// plain value, is compared as a whole.
+fn diff_items(path: &str, old: Option<&Item>, new: Option<&Item>, changes: &mut Vec<FieldChange>) {
+    let old_table = old.and_then(|i| i.as_table_like());
+    let new_table = new.and_then(|i| i.as_table_like());
+    let absent = |item: Option<&Item>| item.is_none_or(Item::is_none);
+    let is_table = (old_table.is_some() || absent(old))
+        && (new_table.is_some() || absent(new))
+        && (old_table.is_some() || new_table.is_some());
+    if is_table {
+        let mut keys: Vec<&str> = new_table
+            .iter()
+            .flat_map(|t| t.iter().map(|(k, _)| k))
+            .collect();
+        for (key, _) in old_table.iter().flat_map(|t| t.iter()) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        for key in keys {
+            let child = if path.is_empty() {
+                path_segment(key)
+            } else {
+                format!("{}.{}", path, path_segment(key))
+            };
+            diff_items(
+                &child,
+                old_table.and_then(|t| t.get(key)),
+                new_table.and_then(|t| t.get(key)),
+                changes,
+            );
+        }
+        return;
+    }
+
+    let old = old.and_then(render_item);
+    let new = new.and_then(render_item);
+    if old != new {
+        changes.push(FieldChange {
+            path: path.to_string(),
+            old,
+            new,
+        });
+    }
+}
+
+/// A value as it would be compared and shown: strings without quotes, anything else as
+/// TOML without surrounding whitespace or comments.
+fn render_item(item: &Item) -> Option<String> {
+    let value = item.as_value()?;
+    Some(match value.as_str() {
+        Some(s) => s.to_string(),
+        None => value.clone().decorated("", "").to_string(),
+    })
+}
+
+/// `key` as a segment of a dotted path, quoted when it isn't a bare key.
+fn path_segment(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_string()
+    } else {
+        format!("{:?}", key)
+    }
+}
+
+/// The manifest as it would be saved.
+impl fmt::Display for CrateEditor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// Write several manifests as a unit. If a write fails, the manifests already written
+/// are restored to their previous contents (best effort) before returning the error.
+pub fn save_all(editors: &[&CrateEditor]) -> Result<()> {
+    save_all_with(editors, |path, content| fs::write(path, content))
+}
+
+fn save_all_with<W>(editors: &[&CrateEditor], mut write: W) -> Result<()>
+where
+    W: FnMut(&Path, &str) -> std::io::Result<()>,
+{
+    let mut written: Vec<(PathBuf, String)> = Vec::new();
+    for editor in editors {
+        let manifest_path = editor.dir()?.join("Cargo.toml");
+        let original = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read Cargo.toml at {:?}", manifest_path))?;
+
+        if let Err(e) = write(&manifest_path, &editor.to_string()) {
+            for (path, content) in written.iter().rev() {
+                if let Err(restore_err) = fs::write(path, content) {
+                    error!("Failed to restore {:?}: {}", path, restore_err);
+                }
+            }
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to write {:?}; reverted {} manifest(s) already written",
+                    manifest_path,
+                    written.len()
+                )
+            });
+        }
+        written.push((manifest_path, original));
+    }
+    Ok(())
+}
+
+/// Rewrite a single dependency entry to `version`, returning whether it changed.
+fn set_item_version(dep_item: &mut Item, version: &Version) -> bool {
+    let new_version = version.to_string();
+
+    if let Some(item) = dep_item.as_table_like_mut() {
+        let Some(slot) = item.get_mut("version") else {
+            return false;
+        };
+        let Some(current) = slot.as_str() else {
+            return false;
+        };
+        let requirement = with_operator_of(current, &new_version);
+        if current == requirement {
+            return false;
+        }
+        set_str_value(slot, &requirement);
+        true
+    } else if let Some(current) = dep_item.as_str() {
+        // Handle simple "dep = '1.0'"
+        let requirement = with_operator_of(current, &new_version);
+        if current == requirement {
+            return false;
+        }
+        set_str_value(dep_item, &requirement);
+        true
+    } else {
+        false
+    }
+}
+
+/// Point a git dependency entry at the tag for `version`, returning whether it changed.
+/// A branch is replaced by the `tag_prefix`ed tag; an existing tag keeps `tag_prefix`
+/// only if it had it.
+fn set_item_tag(dep_item: &mut Item, version: &Version, tag_prefix: &str) -> bool {
+    let Some(item) = dep_item.as_table_like_mut() else {
+        return false;
+    };
+    let tag = format!("{}{}", tag_prefix, version);
+
+    if item.contains_key("branch") {
+        item.remove("branch");
+        item.insert("tag", value(tag));
+        return true;
+    }
+    let Some(slot) = item.get_mut("tag") else {
+        return false;
+    };
+    let Some(current) = slot.as_str() else {
+        return false;
+    };
+    let new_tag = if current.starts_with(tag_prefix) {
+        tag
+    } else {
+        version.to_string()
+    };
+    if current == new_tag {
+        return false;
+    }
+    set_str_value(slot, &new_tag);
+    true
+}
+
+/// `version` with the comparison operator (`^`, `~`, `>=` or `=`) that `requirement`
+/// starts with, so `^0.1` moves to `^0.2.0` rather than to the bare `0.2.0`.
+fn with_operator_of(requirement: &str, version: &str) -> String {
+    let trimmed = requirement.trim_start();
+    let operator = [">=", "^", "~", "="]
+        .into_iter()
+        .find(|op| trimmed.starts_with(op));
+    match operator {
+        Some(op) => {
+            let rest = &trimmed[op.len()..];
+            let spacing = &rest[..rest.len() - rest.trim_start().len()];
+            format!("{}{}{}", op, spacing, version)
+        }
+        None => version.to_string(),
+    }
+}
+
+/// Whether `item` is a `{ workspace = true }` inheritance marker.
+fn is_workspace_inherited(item: &Item) -> bool {
+    item.as_table_like()
+        .and_then(|t| t.get("workspace"))
+        .and_then(|w| w.as_bool())
+        .unwrap_or(false)
+}
+
+/// Replace the string stored in `slot`, keeping the whitespace and comments around it.
+fn set_str_value(slot: &mut Item, new: &str) {
+    match slot.as_value_mut() {
+        Some(current) => {
+            let decor = current.decor().clone();
+            *current = Value::from(new);
+            *current.decor_mut() = decor;
+        }
+        None => *slot = value(new),
+    }
+}
+
+/// Rename `old` to `new` in `table`, keeping the order of entries and the key's spacing.
+fn rename_table_key(table: &mut dyn TableLike, old: &str, new: &str) {
+    let keys: Vec<Key> = table
+        .iter()
+        .filter_map(|(key, _)| table.key(key).cloned())
+        .collect();
+    for key in keys {
+        let Some(item) = table.remove(key.get()) else {
+            continue;
+        };
+        let key = if key.get() == old {
+            Key::new(new).with_leaf_decor(key.leaf_decor().clone())
+        } else {
+            key
+        };
+        table.entry_format(&key).or_insert(item);
     }
 }
 
+/// The sorted, deduplicated packages among `items` that are workspace `members`.
+fn member_names(items: Vec<(&str, &Item)>, members: &HashSet<String>) -> Vec<String> {
+    let mut deps: Vec<String> = items
+        .into_iter()
+        .map(|(key, item)| dependency_package_name(key, item))
+        .filter(|name| members.contains(*name))
+        .map(|name| name.to_string())
+        .collect();
+    deps.sort();
+    deps.dedup();
+    deps
+}
+
+/// The package a dependency entry refers to, honoring `package = "..."` renames.
+fn dependency_package_name<'a>(key: &'a str, item: &'a Item) -> &'a str {
+    item.as_table_like()
+        .and_then(|t| t.get("package"))
+        .and_then(|p| p.as_str())
+        .unwrap_or(key)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::log::Verbosity;
+    use crate::log::scoped::{CaptureLogger, with_logger};
+    use std::sync::Arc;
 
     #[test]
     fn test_bump_version() -> Result<()> {
@@ -118,7 +959,7 @@ other-crate = { version = "0.1.0" }
         editor.bump_version(&new_version)?;
 
         // Save
-        editor.save()?;
+        save_all(&[&editor])?;
 
         // Verify
         let content = fs::read_to_string(manifest_path)?;
@@ -127,12 +968,52 @@ other-crate = { version = "0.1.0" }
         Ok(())
     }
 
+    #[test]
+    fn test_update_keeps_requirement_operator() -> Result<()> {
+        let mut editor = CrateEditor::from_content(
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[dependencies]
+caret = { version = "^0.1.0", path = "../caret" }
+bare = { version = "0.1.0", path = "../bare" }
+tilde = "~0.1"
+at-least = ">= 0.1.0"
+exact = { version = "=0.1.0" }
+"#,
+        )?;
+        let members: Vec<String> = ["caret", "bare", "tilde", "at-least", "exact"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        editor.update_dependencies(&members, &Version::parse("0.2.0")?, "v")?;
+
+        let content = editor.doc.to_string();
+        assert!(
+            content.contains(r#"caret = { version = "^0.2.0", path = "../caret" }"#),
+            "{}",
+            content
+        );
+        assert!(
+            content.contains(r#"bare = { version = "0.2.0", path = "../bare" }"#),
+            "{}",
+            content
+        );
+        assert!(content.contains(r#"tilde = "~0.2.0""#), "{}", content);
+        assert!(content.contains(r#"at-least = ">= 0.2.0""#), "{}", content);
+        assert!(
+            content.contains(r#"exact = { version = "=0.2.0" }"#),
+            "{}",
+            content
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_update_dependencies() -> Result<()> {
-        let temp_dir = tempfile::tempdir()?;
-        let manifest_path = temp_dir.path().join("Cargo.toml");
-        fs::write(
-            &manifest_path,
+        let mut editor = CrateEditor::from_content(
             r#"[package]
 name = "my-crate"
 version = "0.1.0"
@@ -143,21 +1024,197 @@ dep-b = "0.1.0"
 external-dep = "1.0.0"
 "#,
         )?;
-
-        let mut editor = CrateEditor::new(temp_dir.path())?;
         let new_version = Version::parse("0.2.0")?;
 
         let members = vec!["dep-a".to_string(), "dep-b".to_string()];
-        editor.update_dependencies(&members, &new_version)?;
-        editor.save()?;
+        editor.update_dependencies(&members, &new_version, "v")?;
 
-        let content = fs::read_to_string(manifest_path)?;
+        let content = editor.to_string();
         assert!(content.contains(r#"dep-a = { version = "0.2.0" }"#));
         assert!(content.contains(r#"dep-b = "0.2.0""#));
         assert!(content.contains(r#"external-dep = "1.0.0""#)); // Should not change
 
         Ok(())
     }
+
+    #[test]
+    fn test_update_git_tags_in_dev_and_build_dependencies() -> Result<()> {
+        let mut editor = CrateEditor::from_content(
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[dev-dependencies]
+test-utils = { git = "https://example.com/utils", tag = "v0.1.0" }
+
+[build-dependencies]
+codegen = { git = "https://example.com/codegen", tag = "0.1.0" }
+helper = { git = "https://example.com/helper", branch = "main" }
+"#,
+        )?;
+
+        let members = vec![
+            "test-utils".to_string(),
+            "codegen".to_string(),
+            "helper".to_string(),
+        ];
+        editor.update_dependencies(&members, &Version::parse("0.2.0")?, "v")?;
+
+        let content = editor.to_string();
+        assert!(
+            content
+                .contains(r#"test-utils = { git = "https://example.com/utils", tag = "v0.2.0" }"#)
+        );
+        assert!(
+            content.contains(r#"codegen = { git = "https://example.com/codegen", tag = "0.2.0" }"#)
+        );
+        assert!(
+            content.contains(r#"helper = { git = "https://example.com/helper", tag = "v0.2.0" }"#)
+        );
+
+        // Tags follow the workspace's tag prefix
+        let mut editor = CrateEditor::from_content(
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[dependencies]
+codegen = { git = "https://example.com/codegen", tag = "release-0.1.0" }
+helper = { git = "https://example.com/helper", branch = "main" }
+"#,
+        )?;
+        editor.update_dependencies(&members, &Version::parse("0.2.0")?, "release-")?;
+        let content = editor.to_string();
+        assert!(
+            content.contains(
+                r#"codegen = { git = "https://example.com/codegen", tag = "release-0.2.0" }"#
+            ),
+            "{}",
+            content
+        );
+        assert!(
+            content.contains(
+                r#"helper = { git = "https://example.com/helper", tag = "release-0.2.0" }"#
+            ),
+            "{}",
+            content
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_patch_tables() -> Result<()> {
+        let mut editor = CrateEditor::from_content(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+core = "0.1.0"
+
+[patch.crates-io]
+core = { git = "https://example.com/core", tag = "v0.1.0" }
+serde = { git = "https://example.com/serde", tag = "v1.0.0" }
+
+[patch."https://example.com/net"]
+net = { git = "https://example.com/net-fork", tag = "v0.1.0" }
+"#,
+        )?;
+
+        let members = vec!["core".to_string(), "net".to_string()];
+        editor.update_dependencies(&members, &Version::parse("0.2.0")?, "v")?;
+
+        let content = editor.to_string();
+        assert!(content.contains(r#"core = "0.2.0""#), "{}", content);
+        assert!(
+            content.contains(r#"core = { git = "https://example.com/core", tag = "v0.2.0" }"#),
+            "{}",
+            content
+        );
+        assert!(
+            content.contains(r#"net = { git = "https://example.com/net-fork", tag = "v0.2.0" }"#),
+            "{}",
+            content
+        );
+        assert!(
+            content.contains(r#"serde = { git = "https://example.com/serde", tag = "v1.0.0" }"#),
+            "{}",
+            content
+        );
+
+        // Patches aren't dependencies of the crate
+        let names: HashSet<String> = members.into_iter().collect();
+        assert_eq!(editor.get_dependencies(&names), ["core"]);
+
+        // but they follow renames
+        assert!(editor.rename_dependency("net", "net2")?);
+        assert!(
+            editor
+                .to_string()
+                .contains(r#"net2 = { git = "https://example.com/net-fork""#),
+            "{}",
+            editor
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_target_specific_dependencies() -> Result<()> {
+        let mut editor = CrateEditor::from_content(
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[target.'cfg(windows)'.dependencies]
+win-support = "0.1.0"
+
+[target.x86_64-unknown-linux-gnu.dev-dependencies]
+linux-tests = { git = "https://example.com/linux", tag = "v0.1.0" }
+"#,
+        )?;
+
+        let members: HashSet<String> =
+            ["win-support".to_string(), "linux-tests".to_string()].into();
+        assert_eq!(
+            editor.get_dependencies(&members),
+            vec!["linux-tests", "win-support"]
+        );
+
+        editor.update_dependencies(&Vec::from_iter(members), &Version::parse("0.2.0")?, "v")?;
+        let content = editor.to_string();
+        assert!(
+            content.contains("[target.'cfg(windows)'.dependencies]\nwin-support = \"0.2.0\"\n")
+        );
+        assert!(
+            content
+                .contains(r#"linux-tests = { git = "https://example.com/linux", tag = "v0.2.0" }"#)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_content_round_trip() -> Result<()> {
+        let content = "[package]\nname = \"a\" # the name\nversion = \"0.1.0\"\n";
+        let mut editor = CrateEditor::from_content(content)?;
+        assert_eq!(editor.to_string(), content);
+        assert_eq!(editor.path(), None);
+
+        editor.bump_version(&Version::new(1, 0, 0))?;
+        assert_eq!(
+            editor.to_string(),
+            "[package]\nname = \"a\" # the name\nversion = \"1.0.0\"\n"
+        );
+        // There's nowhere to save it
+        assert!(save_all(&[&editor]).is_err());
+
+        assert!(CrateEditor::from_content("[package\n").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_update_git_dependencies() -> Result<()> {
         let temp_dir = tempfile::tempdir()?;
@@ -179,8 +1236,8 @@ git-dep-no-v = { git = "https://example.com/repo2", tag = "0.1.0" }
 
         let members = vec!["git-dep-v".to_string(), "git-dep-no-v".to_string()];
 
-        editor.update_dependencies(&members, &new_version)?;
-        editor.save()?;
+        editor.update_dependencies(&members, &new_version, "v")?;
+        save_all(&[&editor])?;
 
         let content = fs::read_to_string(manifest_path)?;
         assert!(content.contains(r#"tag = "v0.2.0""#));
@@ -208,8 +1265,8 @@ git-dep = { git = "https://example.com/repo", branch = "master" }
 
         let members = vec!["git-dep".to_string()];
 
-        editor.update_dependencies(&members, &new_version)?;
-        editor.save()?;
+        editor.update_dependencies(&members, &new_version, "v")?;
+        save_all(&[&editor])?;
 
         let content = fs::read_to_string(manifest_path)?;
         assert!(!content.contains("branch"));
@@ -217,4 +1274,648 @@ git-dep = { git = "https://example.com/repo", branch = "master" }
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_dependency_pins() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[dependencies]
+dep-a = "0.1.0"
+renamed = { package = "dep-b", git = "https://example.com/b", tag = "v0.1.0" }
+external-dep = "1.0.0"
+
+[dev-dependencies.dep-c]
+version = "0.3.0"
+"#,
+        )?;
+
+        let editor = CrateEditor::new(temp_dir.path())?;
+        assert_eq!(editor.get_version().as_deref(), Some("0.1.0"));
+
+        let members: HashSet<String> = ["dep-a", "dep-b", "dep-c"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let pins = editor.get_dependency_pins(&members);
+        assert_eq!(
+            pins,
+            vec![
+                ("dep-a".to_string(), "0.1.0".to_string()),
+                ("dep-b".to_string(), "v0.1.0".to_string()),
+                ("dep-c".to_string(), "0.3.0".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_dependency_version() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manifest_path = temp_dir.path().join("Cargo.toml");
+        fs::write(
+            &manifest_path,
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[dependencies]
+dep-a = "0.1.0"
+other = "0.1.0"
+
+[build-dependencies.dep-a]
+version = "0.1.0"
+"#,
+        )?;
+
+        let mut editor = CrateEditor::new(temp_dir.path())?;
+        let new_version = Version::parse("0.3.0")?;
+
+        assert!(editor.set_dependency_version("dep-a", &new_version, "v")?);
+        // Already up to date, nothing left to change
+        assert!(!editor.set_dependency_version("dep-a", &new_version, "v")?);
+        assert!(!editor.set_dependency_version("missing", &new_version, "v")?);
+        save_all(&[&editor])?;
+
+        let content = fs::read_to_string(manifest_path)?;
+        assert!(content.contains(r#"dep-a = "0.3.0""#));
+        assert!(content.contains("[build-dependencies.dep-a]\nversion = \"0.3.0\""));
+        assert!(content.contains(r#"other = "0.1.0""#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bump_preserves_formatting() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manifest_path = temp_dir.path().join("Cargo.toml");
+        fs::write(
+            &manifest_path,
+            r#"# Top comment
+[package]
+name = "my-crate"   # the name
+version = "0.1.0"   # bumped by meta
+
+[dependencies]
+# internal crates
+dep-a = "0.1.0" # simple
+dep-b = {path="../b",  version = "0.1.0" ,features=["x"]}
+dep-c = { git = "https://example.com/c", tag = "v0.1.0", default-features = false }
+external = "1.0"   # untouched
+
+[dependencies.dep-d]
+version = "0.1.0" # table form
+path = "../d"
+"#,
+        )?;
+
+        let mut editor = CrateEditor::new(temp_dir.path())?;
+        let new_version = Version::parse("0.2.0")?;
+        let members: Vec<String> = ["my-crate", "dep-a", "dep-b", "dep-c", "dep-d"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        editor.bump_version(&new_version)?;
+        editor.update_dependencies(&members, &new_version, "v")?;
+        save_all(&[&editor])?;
+
+        // Only the targeted values change, byte-for-byte
+        let content = fs::read_to_string(manifest_path)?;
+        assert_eq!(
+            content,
+            r#"# Top comment
+[package]
+name = "my-crate"   # the name
+version = "0.2.0"   # bumped by meta
+
+[dependencies]
+# internal crates
+dep-a = "0.2.0" # simple
+dep-b = {path="../b",  version = "0.2.0" ,features=["x"]}
+dep-c = { git = "https://example.com/c", tag = "v0.2.0", default-features = false }
+external = "1.0"   # untouched
+
+[dependencies.dep-d]
+version = "0.2.0" # table form
+path = "../d"
+"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_dependencies() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "my-crate"
+version = "0.1.0"
+
+[dependencies]
+dep-a = "0.1.0"
+alias = { package = "dep-b", path = "../b" }
+serde = "1.0"
+
+[dev-dependencies]
+dep-a = { path = "../a" }
+
+[build-dependencies.dep-c]
+path = "../c"
+"#,
+        )?;
+
+        let editor = CrateEditor::new(temp_dir.path())?;
+        let members: HashSet<String> = ["my-crate", "dep-a", "dep-b", "dep-c", "dep-d"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(
+            editor.get_dependencies(&members),
+            vec!["dep-a", "dep-b", "dep-c"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_dependency_spec() -> Result<()> {
+        let editor = CrateEditor::from_content(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+plain = "1.2"
+table = { version = "0.3", features = ["std"] }
+local = { path = "../local", version = "0.1.0" }
+tagged = { git = "https://github.com/org/tagged", tag = "v0.2.0" }
+branched = { git = "https://github.com/org/branched", branch = "main" }
+aliased = { package = "pinned", git = "https://github.com/org/pinned", rev = "abc123" }
+shared = { workspace = true }
+
+[target.'cfg(unix)'.dev-dependencies]
+head = { git = "https://github.com/org/head", version = "1.0" }
+"#,
+        )?;
+
+        let git = |url: &str, reference: GitReference, version: Option<&str>| {
+            Some(DependencyKind::Git {
+                url: url.to_string(),
+                reference,
+                version: version.map(String::from),
+            })
+        };
+        assert_eq!(
+            editor.get_dependency_spec("plain"),
+            Some(DependencyKind::Registry {
+                version: "1.2".to_string()
+            })
+        );
+        assert_eq!(
+            editor.get_dependency_spec("table"),
+            Some(DependencyKind::Registry {
+                version: "0.3".to_string()
+            })
+        );
+        assert_eq!(
+            editor.get_dependency_spec("local"),
+            Some(DependencyKind::Path {
+                path: "../local".to_string(),
+                version: Some("0.1.0".to_string())
+            })
+        );
+        assert_eq!(
+            editor.get_dependency_spec("tagged"),
+            git(
+                "https://github.com/org/tagged",
+                GitReference::Tag("v0.2.0".to_string()),
+                None
+            )
+        );
+        assert_eq!(
+            editor.get_dependency_spec("branched"),
+            git(
+                "https://github.com/org/branched",
+                GitReference::Branch("main".to_string()),
+                None
+            )
+        );
+        assert_eq!(
+            editor.get_dependency_spec("pinned"),
+            git(
+                "https://github.com/org/pinned",
+                GitReference::Rev("abc123".to_string()),
+                None
+            )
+        );
+        assert_eq!(editor.get_dependency_spec("aliased"), None);
+        assert_eq!(
+            editor.get_dependency_spec("shared"),
+            Some(DependencyKind::Workspace)
+        );
+        assert_eq!(
+            editor.get_dependency_spec("head"),
+            git(
+                "https://github.com/org/head",
+                GitReference::DefaultBranch,
+                Some("1.0")
+            )
+        );
+        assert_eq!(editor.get_dependency_spec("missing"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_registry_dependency_version() -> Result<()> {
+        let mut editor = CrateEditor::from_content(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+tokio = { version = "1.38", features = ["full"] }
+serde = "1"
+
+[dev-dependencies]
+tokio = { path = "../tokio" }
+
+[target.'cfg(unix)'.dependencies]
+tokio = "1.38"
+
+[build-dependencies]
+tokio = { git = "https://github.com/tokio-rs/tokio", tag = "tokio-1.38.0" }
+"#,
+        )?;
+
+        let logger = Arc::new(CaptureLogger::default());
+        let changed = with_logger(Verbosity::Normal, logger.clone(), || {
+            editor.set_registry_dependency_version("tokio", &Version::parse("1.40.0")?)
+        })?;
+        assert!(changed);
+        assert_eq!(
+            editor.to_string(),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+tokio = { version = "1.40.0", features = ["full"] }
+serde = "1"
+
+[dev-dependencies]
+tokio = { path = "../tokio" }
+
+[target.'cfg(unix)'.dependencies]
+tokio = "1.40.0"
+
+[build-dependencies]
+tokio = { git = "https://github.com/tokio-rs/tokio", tag = "tokio-1.38.0" }
+"#
+        );
+        let warnings: Vec<String> = logger.messages().into_iter().map(|(_, m)| m).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Warning: app has a path dependency on tokio; left unchanged",
+                "Warning: app has a git dependency on tokio; left unchanged",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_dependency() -> Result<()> {
+        let content = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = "1"
+old_name = { path = "../old_name", version = "0.1.0" }
+alias = { package = "old_name", git = "https://github.com/org/old_name", tag = "v0.1.0" }
+log = "0.4"
+
+[dev-dependencies.old_name]
+path = "../old_name"
+
+[target.'cfg(unix)'.build-dependencies]
+old_name = "0.1.0"
+"#;
+        let mut editor = CrateEditor::from_content(content)?;
+        assert!(editor.rename_dependency("old_name", "new_name")?);
+        assert_eq!(
+            editor.to_string(),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = "1"
+new_name = { path = "../old_name", version = "0.1.0" }
+alias = { package = "new_name", git = "https://github.com/org/old_name", tag = "v0.1.0" }
+log = "0.4"
+
+[dev-dependencies.new_name]
+path = "../old_name"
+
+[target.'cfg(unix)'.build-dependencies]
+new_name = "0.1.0"
+"#
+        );
+        assert!(!editor.rename_dependency("old_name", "new_name")?);
+
+        editor.rename_package("app2")?;
+        assert_eq!(editor.get_package_name().as_deref(), Some("app2"));
+        assert!(editor.rename_package("not a name").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_against() -> Result<()> {
+        let old = r#"[package]
+name = "app" # the app
+version = "0.1.0"
+
+[dependencies]
+core = { path = "../core", version = "0.1.0" }
+legacy = "0.3"
+serde = "1"
+
+[target.'cfg(unix)'.dependencies]
+nix = "0.27"
+"#;
+        let mut editor = CrateEditor::from_content(old)?;
+        assert!(editor.diff_against(old)?.is_empty());
+
+        editor.bump_version(&Version::new(0, 2, 0))?;
+        editor.set_dependency_version("core", &Version::new(0, 2, 0), "v")?;
+        editor.remove_dependency("legacy");
+        editor.doc["dependencies"]["tokio"] = value("1");
+        editor.doc["features"]["default"] = value(toml_edit::Array::from_iter(["std"]));
+
+        let change = |path: &str, old: Option<&str>, new: Option<&str>| FieldChange {
+            path: path.to_string(),
+            old: old.map(String::from),
+            new: new.map(String::from),
+        };
+        assert_eq!(
+            editor.diff_against(old)?,
+            [
+                change("package.version", Some("0.1.0"), Some("0.2.0")),
+                change("dependencies.core.version", Some("0.1.0"), Some("0.2.0")),
+                change("dependencies.tokio", None, Some("1")),
+                change("dependencies.legacy", Some("0.3"), None),
+                change("features.default", None, Some(r#"["std"]"#)),
+            ]
+        );
+        assert_eq!(
+            editor.diff_against(old)?[0].to_string(),
+            "package.version: 0.1.0 -> 0.2.0"
+        );
+        assert_eq!(
+            change("target.\"cfg(unix)\".dependencies.nix", Some("0.27"), None).to_string(),
+            r#"- target."cfg(unix)".dependencies.nix (was 0.27)"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_features() -> Result<()> {
+        let editor = CrateEditor::from_content(
+            r#"[package]
+name = "a"
+
+[features]
+default = ["std"]
+std = ["serde?/std"]
+tracing = ["dep:tracing"]
+"#,
+        )?;
+        let features = editor.get_features();
+        assert_eq!(
+            features.into_iter().collect::<Vec<_>>(),
+            [
+                ("default".to_string(), vec!["std".to_string()]),
+                ("std".to_string(), vec!["serde?/std".to_string()]),
+                ("tracing".to_string(), vec!["dep:tracing".to_string()]),
+            ]
+        );
+
+        assert!(
+            CrateEditor::from_content("[package]\nname = \"b\"\n")?
+                .get_features()
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_edition() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\" # keep\n",
+        )?;
+
+        let mut editor = CrateEditor::new(root)?;
+        editor.set_edition("2024")?;
+        save_all(&[&editor])?;
+        assert_eq!(
+            fs::read_to_string(root.join("Cargo.toml"))?,
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2024\" # keep\n"
+        );
+
+        // Crates without an edition get one
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"a\"\n")?;
+        let mut editor = CrateEditor::new(root)?;
+        editor.set_edition("2018")?;
+        assert_eq!(
+            editor.to_string(),
+            "[package]\nname = \"a\"\nedition = \"2018\"\n"
+        );
+
+        let err = editor.set_edition("2027").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown edition '2027' (expected one of 2015, 2018, 2021, 2024)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_edition_inherited_from_workspace() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nedition = \"2021\"\n",
+        )?;
+        fs::create_dir(root.join("a"))?;
+        let member_manifest =
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition.workspace = true\n";
+        fs::write(root.join("a/Cargo.toml"), member_manifest)?;
+
+        let mut editor = CrateEditor::new(&root.join("a"))?;
+        let edit = editor
+            .set_edition("2024")?
+            .expect("edit for the workspace root");
+        assert_eq!(edit.root, root.canonicalize()?);
+        let mut root_editor = CrateEditor::new(&edit.root)?;
+        root_editor.apply_root_edit(&edit);
+        save_all(&[&editor, &root_editor])?;
+
+        assert_eq!(
+            fs::read_to_string(root.join("a/Cargo.toml"))?,
+            member_manifest
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("Cargo.toml"))?,
+            "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nedition = \"2024\"\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_package_field() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense = \"MIT\" # SPDX\n",
+        )?;
+
+        let mut editor = CrateEditor::new(root)?;
+        editor.set_package_field("license", Value::from("MIT OR Apache-2.0"))?;
+        editor.set_package_field("authors", "[\"A <a@example.com>\", \"B\"]".parse()?)?;
+        assert_eq!(
+            editor.to_string(),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense = \"MIT OR Apache-2.0\" # SPDX\nauthors = [\"A <a@example.com>\", \"B\"]\n"
+        );
+
+        assert!(
+            editor
+                .set_package_field("version", Value::from("9.9.9"))
+                .is_err()
+        );
+        assert_eq!(editor.get_version().as_deref(), Some("0.1.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        let validate = |manifest: &str| -> Result<Result<()>> {
+            fs::write(root.join("Cargo.toml"), manifest)?;
+            Ok(CrateEditor::new(root)?.validate())
+        };
+
+        assert!(validate("[package]\nname = \"a\"\nversion = \"0.1.0\"\n")?.is_ok());
+        assert!(validate("[package]\nname = \"a\"\nversion.workspace = true\n")?.is_ok());
+
+        let message =
+            |manifest| -> Result<String> { Ok(validate(manifest)?.unwrap_err().to_string()) };
+        assert_eq!(message("[workspace]\n")?, "missing [package] section");
+        assert_eq!(
+            message("[package]\nversion = \"1.0\"\n")?,
+            "missing package name; invalid version '1.0': unexpected end of input while parsing minor version number"
+        );
+        assert_eq!(
+            message("[package]\nname = \"a\"\n")?,
+            "missing package version"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_points_at_line() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"a\"\nversion = 0.1.0\"\n",
+        )?;
+
+        let Err(err) = CrateEditor::new(temp_dir.path()) else {
+            panic!("expected a parse error");
+        };
+        let err = err.to_string();
+        assert!(err.starts_with("Failed to parse Cargo.toml at "), "{}", err);
+        assert!(
+            err.contains("Cargo.toml\": TOML parse error at line 3, column 14"),
+            "{}",
+            err
+        );
+        assert!(err.contains("3 | version = 0.1.0\""), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_all_rolls_back_on_failure() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        let mut editors = Vec::new();
+        for name in ["a", "b", "c"] {
+            let dir = root.join(name);
+            fs::create_dir(&dir)?;
+            fs::write(
+                dir.join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name),
+            )?;
+            let mut editor = CrateEditor::new(&dir)?;
+            editor.bump_version(&Version::new(0, 2, 0))?;
+            editors.push(editor);
+        }
+        let refs: Vec<&CrateEditor> = editors.iter().collect();
+
+        let err = save_all_with(&refs, |path, content| {
+            if path.starts_with(root.join("b")) {
+                return Err(std::io::Error::other("disk full"));
+            }
+            fs::write(path, content)
+        })
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("disk full"), "{:#}", err);
+
+        for name in ["a", "b", "c"] {
+            let content = fs::read_to_string(root.join(name).join("Cargo.toml"))?;
+            assert!(
+                content.contains("version = \"0.1.0\""),
+                "{}: {}",
+                name,
+                content
+            );
+        }
+
+        save_all(&refs)?;
+        for name in ["a", "b", "c"] {
+            let content = fs::read_to_string(root.join(name).join("Cargo.toml"))?;
+            assert!(
+                content.contains("version = \"0.2.0\""),
+                "{}: {}",
+                name,
+                content
+            );
+        }
+
+        Ok(())
+    }
 }
@@ -1,207 +1,1107 @@
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Output, Stdio};
+use std::time::{Duration, Instant};
 
-pub fn group_members_by_repo(members: &[PathBuf]) -> Result<HashMap<PathBuf, Vec<PathBuf>>> {
-    let mut repo_map: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+/// Group members by the git repository containing them.
+///
+/// Members are canonicalized first, so a member that is a symlink into another
+/// repository is grouped under (and reported as) its real location. Both the repo
+/// roots and the member paths in the result are canonical.
+///
+/// When `boundary` is given (normally the workspace root), repositories above it are
+/// ignored so members that aren't in a repo of their own don't get attributed to some
+/// unrelated enclosing repository.
+pub fn group_members_by_repo(
+    members: &[PathBuf],
+    boundary: Option<&Path>,
+) -> Result<BTreeMap<PathBuf, Vec<PathBuf>>> {
+    let mut repo_map: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
 
     for member in members {
-        if let Some(git_root) = find_git_root(member)? {
-            repo_map.entry(git_root).or_default().push(member.clone());
+        let canonical = member.canonicalize().with_context(|| {
+            format!(
+                "Failed to canonicalize path '{}'. Does the directory exist?",
+                member.display()
+            )
+        })?;
+
+        if let Some(git_root) = find_git_root(&canonical, boundary)? {
+            repo_map.entry(git_root).or_default().push(canonical);
         } else {
-            println!("Warning: No git repository found for member {:?}", member);
+            warn!("Warning: No git repository found for member {:?}", member);
         }
     }
 
+    // Repositories come out sorted by path; keep their members sorted too so runs are
+    // reproducible
+    for members in repo_map.values_mut() {
+        members.sort();
+        members.dedup();
+    }
     Ok(repo_map)
 }
 
-fn find_git_root(path: &Path) -> Result<Option<PathBuf>> {
-    let mut current = path.canonicalize().with_context(|| {
-        format!(
-            "Failed to canonicalize path '{}'. Does the directory exist?",
-            path.display()
-        )
-    })?;
-
-    loop {
-        if current.join(".git").exists() {
-            return Ok(Some(current));
+/// Walk up from an already canonicalized `path` to the nearest directory with a `.git`.
+///
+/// The search includes `boundary` itself but goes no further. Paths that aren't under
+/// `boundary` at all (e.g. symlinked members living elsewhere) are searched without limit.
+fn find_git_root(path: &Path, boundary: Option<&Path>) -> Result<Option<PathBuf>> {
+    let boundary = boundary.filter(|b| path.starts_with(b));
+    for dir in path.ancestors() {
+        if resolve_git_dir(dir)?.is_some() {
+            return Ok(Some(dir.to_path_buf()));
         }
-
-        if let Some(parent) = current.parent() {
-            current = parent.to_path_buf();
-        } else {
-            return Ok(None);
+        if Some(dir) == boundary {
+            break;
         }
     }
+    Ok(None)
 }
 
-pub fn create_branch(repo_path: &Path, name: &str) -> Result<()> {
-    println!("Creating/Switching to branch '{}' in {:?}", name, repo_path);
-    // try checkout first
-    let status = Command::new("git")
-        .current_dir(repo_path)
-        .args(&["checkout", name])
-        .output()?;
+/// The git directory backing `dir`, if `dir` is the top of a working tree.
+///
+/// Linked worktrees and submodules have a `.git` file containing `gitdir: <path>`
+/// instead of a directory; the pointer is followed so a stale one is reported here
+/// rather than as a confusing failure of some later git command.
+fn resolve_git_dir(dir: &Path) -> Result<Option<PathBuf>> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Ok(Some(dot_git));
+    }
+    if !dot_git.is_file() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&dot_git)
+        .with_context(|| format!("Failed to read {}", dot_git.display()))?;
+    let pointer = content
+        .lines()
+        .find_map(|line| line.strip_prefix("gitdir:"))
+        .map(str::trim)
+        .with_context(|| format!("{} has no 'gitdir:' line", dot_git.display()))?;
 
-    if !status.status.success() {
-        // Create new branch
-        run_git_cmd(repo_path, &["checkout", "-b", name])?;
+    // Relative pointers (used by submodules) are relative to the directory holding .git
+    let git_dir = dir.join(pointer);
+    if !git_dir.is_dir() {
+        anyhow::bail!(
+            "{} points to missing git directory {}",
+            dot_git.display(),
+            git_dir.display()
+        );
     }
-    Ok(())
+    Ok(Some(git_dir))
 }
 
-pub fn checkout_branch(repo_path: &Path, name: &str) -> Result<()> {
-    println!("Checking out '{}' in {:?}", name, repo_path);
-    run_git_cmd(repo_path, &["checkout", name])
+/// Prefix of stash messages created by `meta stash`.
+const STASH_MARKER: &str = "meta-stash";
+
+/// Extra settings for [`GitContext::commit`].
+#[derive(Clone, Debug, Default)]
+pub struct CommitOptions {
+    /// Rewrite the previous commit instead of creating a new one.
+    pub amend: bool,
+    /// Add a `Signed-off-by` line for the committer.
+    pub sign_off: bool,
+    /// Trailers such as `Co-authored-by: Name <email>`, appended in order.
+    pub trailers: Vec<String>,
+    /// Commit even when nothing changed, e.g. to mark a release point.
+    pub allow_empty: bool,
+    /// Take the message from this file (`git commit -F`) instead of `message`.
+    pub message_file: Option<PathBuf>,
 }
 
-pub fn merge_branch(repo_path: &Path, branch: &str) -> Result<()> {
-    println!("Merging '{}' in {:?}", branch, repo_path);
-    run_git_cmd(repo_path, &["merge", branch])
+/// How often to retry git commands that talk to a remote when they fail transiently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    /// Wait before the first retry; doubled for each further attempt.
+    pub delay: Duration,
 }
 
-pub fn remove_branch(repo_path: &Path, name: &str, remote: bool) -> Result<()> {
-    println!("Removing branch '{}' in {:?}", name, repo_path);
-    // Local delete
-    let _ = run_git_cmd(repo_path, &["branch", "-D", name]); // Ignore error if not exists locally or currently checked out?
+/// Git operations scoped to a single repository.
+pub struct GitContext {
+    repo_path: PathBuf,
+    remote: String,
+    tag_prefix: String,
+    dry_run: bool,
+    retry: RetryPolicy,
+    timeout: Option<Duration>,
+}
 
-    if remote {
-        println!("Removing remote branch '{}'...", name);
-        // Assuming 'origin' is the remote
-        run_git_cmd(repo_path, &["push", "origin", "--delete", name])?;
+impl GitContext {
+    pub fn new(repo_path: &Path) -> Self {
+        Self {
+            repo_path: repo_path.to_path_buf(),
+            remote: "origin".to_string(),
+            tag_prefix: "v".to_string(),
+            dry_run: false,
+            retry: RetryPolicy::default(),
+            timeout: None,
+        }
     }
-    Ok(())
-}
 
-pub fn push(repo_path: &Path) -> Result<()> {
-    println!("Pushing in {:?}", repo_path);
-    // Get current branch name
-    let output = Command::new("git")
-        .current_dir(repo_path)
-        .args(&["rev-parse", "--abbrev-ref", "HEAD"])
-        .output()?;
-    let branch = String::from_utf8(output.stdout)?.trim().to_string();
+    /// Use `remote` instead of `origin` for network operations.
+    pub fn with_remote(mut self, remote: &str) -> Self {
+        self.remote = remote.to_string();
+        self
+    }
 
-    // Push setting upstream
-    run_git_cmd(repo_path, &["push", "-u", "origin", &branch])
-}
+    /// Prefix version tags with `prefix` instead of `v`.
+    pub fn with_tag_prefix(mut self, prefix: &str) -> Self {
+        self.tag_prefix = prefix.to_string();
+        self
+    }
 
-pub fn pull(repo_path: &Path) -> Result<()> {
-    println!("Pulling in {:?}", repo_path);
-    // Get current branch name
-    let output = Command::new("git")
-        .current_dir(repo_path)
-        .args(&["rev-parse", "--abbrev-ref", "HEAD"])
-        .output()?;
-    let branch = String::from_utf8(output.stdout)?.trim().to_string();
+    /// Retry push/pull/fetch according to `policy`.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
 
-    run_git_cmd(repo_path, &["pull", "origin", &branch])
-}
+    /// Kill git commands that run longer than `timeout`, e.g. when git waits for
+    /// credentials nobody will type. `None` waits forever.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
 
-pub fn fetch(repo_path: &Path) -> Result<()> {
-    println!("Fetching in {:?}", repo_path);
-    run_git_cmd(repo_path, &["fetch", "origin"])
-}
+    pub fn repo_path(&self) -> &Path {
+        &self.repo_path
+    }
 
-pub fn commit(repo_path: &Path, message: &str, files: &[PathBuf]) -> Result<()> {
-    println!("Committing in {:?} with message '{}'", repo_path, message);
+    pub fn remote(&self) -> &str {
+        &self.remote
+    }
 
-    if files.is_empty() {
-        println!("No files to commit in {:?}", repo_path);
-        return Ok(());
+    /// Whether the configured remote exists in this repository.
+    pub fn has_remote(&self) -> Result<bool> {
+        let output = self
+            .command(&["remote", "get-url", &self.remote])
+            .output()?;
+        Ok(output.status.success())
     }
 
-    // 1. Add specific files
-    // Convert paths to be relative to the repo_root (repo_path)
-    let mut args = vec!["add"];
-    let mut relative_paths = Vec::new();
+    /// Print mutating commands instead of running them. Read-only queries still run.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
 
-    for file in files {
-        // We canonicalize to ensure we have an absolute path that matches repo_path's canonical nature.
-        // If the file doesn't exist (e.g. deleted), canonicalize fails.
-        // In the case of version bumping/modification, it should exist.
-        // If it doesn't, we might fallback to just using it as is or skipping.
-        let abs_file = if file.exists() {
-            file.canonicalize().unwrap_or_else(|_| file.to_path_buf())
+    /// Switch to `name`, creating it if it doesn't exist yet. New branches start from
+    /// `base` when given, regardless of where HEAD currently is.
+    pub fn create_branch(&self, name: &str, base: Option<&str>) -> Result<()> {
+        info!(
+            "Creating/Switching to branch '{}' in {:?}",
+            name, self.repo_path
+        );
+        if self.dry_run {
+            // Probing with a real checkout would switch branches, so look at the refs instead
+            if self.branch_exists(name)? {
+                return self.run(&["checkout", name]);
+            }
         } else {
-            // If it doesn't exist, we can't easily strip prefix if it's relative and repo is absolute.
-            // But let's assume it's absolute or relative to CWD.
-            // For now, let's just try to use it as is if canonicalize fails.
-            file.to_path_buf()
+            // try checkout first
+            let output = self
+                .command(&["checkout", name])
+                .env("LC_ALL", "C")
+                .output()?;
+
+            if output.status.success() {
+                return Ok(());
+            }
+
+            // Only a missing branch means we should create it; anything else (dirty tree,
+            // unmerged files, ...) is a real failure that must not be papered over.
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !stderr.contains("did not match any file(s) known to git") {
+                anyhow::bail!("Failed to checkout '{}': {}", name, stderr.trim());
+            }
+        }
+
+        // Create new branch
+        match base {
+            Some(base) => self.run(&["checkout", "-b", name, base]),
+            None => self.run(&["checkout", "-b", name]),
+        }
+    }
+
+    /// Make `name` track the branch of the same name on the remote. When the remote
+    /// doesn't have it yet, the upstream is left for the first `meta push` to set.
+    pub fn track_branch(&self, name: &str) -> Result<()> {
+        let remote_ref = format!("refs/remotes/{}/{}", self.remote, name);
+        let output = self
+            .command(&["rev-parse", "--verify", "--quiet", &remote_ref])
+            .output()?;
+        if !output.status.success() {
+            info!(
+                "'{}' doesn't exist on '{}' yet in {:?}; the first push will set the upstream",
+                name, self.remote, self.repo_path
+            );
+            return Ok(());
+        }
+
+        let upstream = format!("--set-upstream-to={}/{}", self.remote, name);
+        self.run(&["branch", &upstream, name])
+    }
+
+    fn branch_exists(&self, name: &str) -> Result<bool> {
+        let refname = format!("refs/heads/{}", name);
+        let output = self
+            .command(&["rev-parse", "--verify", "--quiet", &refname])
+            .output()?;
+        Ok(output.status.success())
+    }
+
+    /// The branch the remote's HEAD points at, falling back to a local `main` or
+    /// `master` when the remote HEAD isn't known (e.g. the repo wasn't cloned).
+    pub fn default_branch(&self) -> Result<String> {
+        let remote_head = format!("refs/remotes/{}/HEAD", self.remote);
+        let output = self
+            .command(&["symbolic-ref", "--quiet", &remote_head])
+            .output()?;
+        if output.status.success() {
+            let target = String::from_utf8(output.stdout)?;
+            let prefix = format!("refs/remotes/{}/", self.remote);
+            if let Some(branch) = target.trim().strip_prefix(&prefix) {
+                return Ok(branch.to_string());
+            }
+        }
+
+        for candidate in ["main", "master"] {
+            if self.branch_exists(candidate)? {
+                return Ok(candidate.to_string());
+            }
+        }
+        anyhow::bail!(
+            "Could not determine the default branch of {:?}: {} is not set and there is no main or master branch",
+            self.repo_path,
+            remote_head
+        )
+    }
+
+    /// Bring submodules in line with the checked-out commit (`git submodule update
+    /// --init --recursive`). Repositories without a `.gitmodules` are left alone.
+    pub fn update_submodules(&self) -> Result<()> {
+        if !self.repo_path.join(".gitmodules").is_file() {
+            verbose!("No submodules in {:?}", self.repo_path);
+            return Ok(());
+        }
+        info!("Updating submodules in {:?}", self.repo_path);
+        self.run(&["submodule", "update", "--init", "--recursive"])
+    }
+
+    pub fn checkout_branch(&self, name: &str) -> Result<()> {
+        info!("Checking out '{}' in {:?}", name, self.repo_path);
+        self.run(&["checkout", name])
+    }
+
+    pub fn merge_branch(&self, branch: &str, no_ff: bool) -> Result<()> {
+        info!("Merging '{}' in {:?}", branch, self.repo_path);
+        let result = if no_ff {
+            self.run(&["merge", "--no-ff", branch])
+        } else {
+            self.run(&["merge", branch])
         };
 
-        match abs_file.strip_prefix(repo_path) {
-            Ok(rel) => relative_paths.push(rel.to_string_lossy().to_string()),
-            Err(_) => {
-                // If we can't strip prefix, maybe it's already relative or outside repo?
-                // Just use the path as provided.
-                relative_paths.push(file.to_string_lossy().to_string());
+        if let Err(e) = result {
+            let conflicts = self.conflicted_files()?;
+            if conflicts.is_empty() {
+                return Err(e);
             }
+            anyhow::bail!(
+                "Merge of '{}' has conflicts in: {}",
+                branch,
+                conflicts.join(", ")
+            );
         }
+        Ok(())
     }
 
-    args.extend(relative_paths.iter().map(|s| s.as_str()));
+    /// Files left unmerged by a failed merge or rebase.
+    fn conflicted_files(&self) -> Result<Vec<String>> {
+        let output = self
+            .command(&["diff", "--name-only", "--diff-filter=U"])
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .map(|l| l.to_string())
+            .collect())
+    }
 
-    run_git_cmd(repo_path, &args)?;
+    /// Abort an in-progress merge. Repos that aren't mid-merge are left alone.
+    pub fn abort_merge(&self) -> Result<()> {
+        if !self.is_merging()? {
+            info!("No merge in progress in {:?}, skipping", self.repo_path);
+            return Ok(());
+        }
+        info!("Aborting merge in {:?}", self.repo_path);
+        self.run(&["merge", "--abort"])
+    }
 
-    // 2. Commit
-    run_git_cmd(repo_path, &["commit", "-m", message])
-}
+    fn is_merging(&self) -> Result<bool> {
+        // Ask git where MERGE_HEAD lives so linked worktrees resolve correctly
+        let output = self
+            .command(&["rev-parse", "--git-path", "MERGE_HEAD"])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to locate the git directory of {:?}", self.repo_path);
+        }
+        let merge_head = String::from_utf8(output.stdout)?.trim().to_string();
+        Ok(self.repo_path.join(merge_head).exists())
+    }
 
-pub fn create_tag(repo_path: &Path, version: &str) -> Result<()> {
-    println!("Creating tag 'v{}' in {:?}", version, repo_path);
-    let tag_name = format!("v{}", version);
-    run_git_cmd(repo_path, &["tag", &tag_name])
-}
+    /// Delete branch `name` locally (and on the remote with `remote`). Git won't delete
+    /// the checked-out branch, so such a repo is skipped with a warning unless a
+    /// `fallback` branch is given to switch to first.
+    pub fn remove_branch(&self, name: &str, remote: bool, fallback: Option<&str>) -> Result<()> {
+        if self.current_branch()? == name {
+            match fallback {
+                Some(fallback) if fallback != name => self.checkout_branch(fallback)?,
+                _ => {
+                    warn!(
+                        "Warning: '{}' is checked out in {:?}; skipping (use --force to switch away first)",
+                        name, self.repo_path
+                    );
+                    return Ok(());
+                }
+            }
+        }
 
-pub fn push_tag(repo_path: &Path, version: &str) -> Result<()> {
-    println!("Pushing tag 'v{}' in {:?}", version, repo_path);
-    let tag_name = format!("v{}", version);
-    run_git_cmd(repo_path, &["push", "origin", &tag_name])
-}
+        info!("Removing branch '{}' in {:?}", name, self.repo_path);
+        // Local delete; the branch may only exist on the remote
+        let _ = self.run(&["branch", "-D", name]);
 
-pub fn remove_tag(repo_path: &Path, name: &str, remote: bool) -> Result<()> {
-    println!("Removing tag '{}' in {:?}", name, repo_path);
-    let _ = run_git_cmd(repo_path, &["tag", "-d", name]);
+        if remote {
+            info!("Removing remote branch '{}'...", name);
+            self.run(&["push", &self.remote, "--delete", name])?;
+        }
+        Ok(())
+    }
 
-    if remote {
-        run_git_cmd(repo_path, &["push", "origin", "--delete", name])?;
+    /// Local branches fully merged into the default branch, except the default branch
+    /// itself, the checked-out branch and the `protected` names.
+    pub fn merged_branches(&self, protected: &[String]) -> Result<Vec<String>> {
+        let base = self.default_branch()?;
+        let current = self.current_branch()?;
+        let output = self
+            .command(&["branch", "--merged", &base, "--format=%(refname:short)"])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to list branches merged into '{}' in {:?}: {}",
+                base,
+                self.repo_path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .map(str::trim)
+            .filter(|b| !b.is_empty() && *b != base && *b != current)
+            .filter(|b| !protected.iter().any(|p| p == b))
+            .map(String::from)
+            .collect())
     }
-    Ok(())
-}
 
-fn run_git_cmd(repo_path: &Path, args: &[&str]) -> Result<()> {
-    let status = Command::new("git")
-        .current_dir(repo_path)
-        .args(args)
-        .status()
-        .context(format!("Failed to execute git {:?}", args))?;
+    /// Delete a merged branch, and with `remote` its counterpart on the remote if there
+    /// is one.
+    pub fn delete_merged_branch(&self, name: &str, remote: bool) -> Result<()> {
+        info!("Removing merged branch '{}' in {:?}", name, self.repo_path);
+        self.run(&["branch", "-d", name])?;
 
-    if !status.success() {
-        anyhow::bail!("Git command failed: {:?}", args);
+        let remote_ref = format!("refs/remotes/{}/{}", self.remote, name);
+        let on_remote = self
+            .command(&["rev-parse", "--verify", "--quiet", &remote_ref])
+            .output()?
+            .status
+            .success();
+        if remote && on_remote {
+            info!("Removing remote branch '{}'...", name);
+            self.run(&["push", &self.remote, "--delete", name])?;
+        }
+        Ok(())
+    }
+
+    /// Push the current branch. A branch with an upstream is pushed there (which may be
+    /// another remote); otherwise it is pushed to the configured remote and that becomes
+    /// its upstream. `force_with_lease` allows replacing rewritten history, e.g. after
+    /// a rebase, as long as the remote branch is where we last saw it.
+    pub fn push(&self, force_with_lease: bool) -> Result<()> {
+        info!("Pushing in {:?}", self.repo_path);
+        let branch = self.current_branch()?;
+        let mut args = vec!["push"];
+        if force_with_lease {
+            args.push("--force-with-lease");
+        }
+
+        match (self.upstream()?, self.ahead_behind()?) {
+            (Some(upstream), Some((ahead, behind))) => {
+                info!(
+                    "{}: {} ahead, {} behind {}",
+                    self.repo_path.display(),
+                    ahead,
+                    behind,
+                    upstream
+                );
+                self.run(&args)
+            }
+            _ => {
+                info!("{}: no upstream configured", self.repo_path.display());
+                args.extend(["-u", &self.remote, &branch]);
+                self.run(&args)
+            }
+        }
+    }
+
+    /// The upstream of the current branch, e.g. `origin/main`, if it has one.
+    pub fn upstream(&self) -> Result<Option<String>> {
+        let output = self
+            .command(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+            .output()?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+    }
+
+    /// Commits HEAD has that its upstream doesn't, and the reverse, as `(ahead, behind)`.
+    /// `None` when the current branch has no upstream.
+    pub fn ahead_behind(&self) -> Result<Option<(usize, usize)>> {
+        if self.upstream()?.is_none() {
+            return Ok(None);
+        }
+
+        let output = self
+            .command(&["rev-list", "--left-right", "--count", "HEAD...@{u}"])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to compare with upstream in {:?}: {}",
+                self.repo_path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let counts = String::from_utf8(output.stdout)?;
+        let mut counts = counts.split_whitespace().map(str::parse::<usize>);
+        match (counts.next(), counts.next()) {
+            (Some(Ok(ahead)), Some(Ok(behind))) => Ok(Some((ahead, behind))),
+            _ => anyhow::bail!("Unexpected rev-list output in {:?}", self.repo_path),
+        }
+    }
+
+    /// Pull the current branch from the remote, rebasing local commits instead of
+    /// merging when `rebase` is set. A conflicting rebase is left in progress.
+    pub fn pull(&self, rebase: bool) -> Result<()> {
+        info!("Pulling in {:?}", self.repo_path);
+        let branch = self.current_branch()?;
+
+        let result = if rebase {
+            self.run(&["pull", "--rebase", &self.remote, &branch])
+        } else {
+            self.run(&["pull", &self.remote, &branch])
+        };
+
+        if let Err(e) = result {
+            let conflicts = self.conflicted_files()?;
+            if conflicts.is_empty() {
+                return Err(e);
+            }
+            anyhow::bail!(
+                "Pull of '{}/{}' has conflicts in: {}",
+                self.remote,
+                branch,
+                conflicts.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    pub fn fetch(&self) -> Result<()> {
+        info!("Fetching in {:?}", self.repo_path);
+        self.run(&["fetch", &self.remote])
+    }
+
+    /// Fast-forward the current branch to its upstream. Diverged branches are reported
+    /// as an error and left untouched.
+    pub fn ff_only(&self) -> Result<()> {
+        let Some((ahead, behind)) = self.ahead_behind()? else {
+            info!("No upstream configured in {:?}, skipping", self.repo_path);
+            return Ok(());
+        };
+
+        if behind == 0 {
+            info!("{} is up to date", self.repo_path.display());
+            return Ok(());
+        }
+        if ahead > 0 {
+            anyhow::bail!(
+                "Cannot fast-forward: branch has diverged from its upstream ({} ahead, {} behind)",
+                ahead,
+                behind
+            );
+        }
+
+        info!(
+            "Fast-forwarding {} by {} commit(s)",
+            self.repo_path.display(),
+            behind
+        );
+        self.run(&["merge", "--ff-only", "@{u}"])
+    }
+
+    /// Stash local changes, tagging the entry so `stash_pop` only restores stashes made
+    /// by `meta`. Clean repos are skipped.
+    pub fn stash(&self, message: Option<&str>, include_untracked: bool) -> Result<()> {
+        let mut status_args = vec!["status", "--porcelain"];
+        if !include_untracked {
+            status_args.push("--untracked-files=no");
+        }
+        let status = self.command(&status_args).output()?;
+        if !status.status.success() {
+            anyhow::bail!("Failed to get status of {:?}", self.repo_path);
+        }
+        if status.stdout.is_empty() {
+            info!("Nothing to stash in {:?}", self.repo_path);
+            return Ok(());
+        }
+
+        info!("Stashing changes in {:?}", self.repo_path);
+        let message = match message {
+            Some(message) => format!("{} {}", STASH_MARKER, message),
+            None => STASH_MARKER.to_string(),
+        };
+        let mut args = vec!["stash", "push", "-m", &message];
+        if include_untracked {
+            args.push("--include-untracked");
+        }
+        self.run(&args)
+    }
+
+    /// Pop the most recent stash made by `stash`. Repos without one are skipped.
+    pub fn stash_pop(&self) -> Result<()> {
+        let output = self
+            .command(&["stash", "list", "--format=%gd %gs"])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to list stashes in {:?}", self.repo_path);
+        }
+        let list = String::from_utf8(output.stdout)?;
+        // Subjects look like "On main: meta-stash message"
+        let entry = list.lines().find_map(|line| {
+            let (stash_ref, subject) = line.split_once(' ')?;
+            let (_, message) = subject.split_once(": ")?;
+            message.starts_with(STASH_MARKER).then_some(stash_ref)
+        });
+
+        let Some(stash_ref) = entry else {
+            info!("No meta stash in {:?}, skipping", self.repo_path);
+            return Ok(());
+        };
+        info!("Restoring stashed changes in {:?}", self.repo_path);
+        self.run(&["stash", "pop", stash_ref])
+    }
+
+    /// Uncommitted changes (or staged ones with `staged`), as a full diff or a `--stat`
+    /// summary. Empty when there is nothing to show.
+    pub fn diff(&self, staged: bool, stat: bool) -> Result<String> {
+        let mut args = vec!["diff"];
+        if staged {
+            args.push("--staged");
+        }
+        if stat {
+            args.push("--stat");
+        }
+
+        let output = self.command(&args).output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git diff failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn current_branch(&self) -> Result<String> {
+        let output = self
+            .command(&["rev-parse", "--abbrev-ref", "HEAD"])
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    /// Stage `files` and commit them. With `amend` the previous commit is rewritten
+    /// instead, keeping its message unless a new one is given.
+    pub fn commit(
+        &self,
+        message: Option<&str>,
+        files: &[PathBuf],
+        options: &CommitOptions,
+    ) -> Result<()> {
+        let amend = options.amend;
+        let message_file = options.message_file.as_deref();
+        let described = match (message, message_file) {
+            (Some(_), Some(_)) => anyhow::bail!("Pass either a commit message or a message file"),
+            (Some(message), None) => Some(format!("message '{}'", message)),
+            (None, Some(file)) => Some(format!("the message in {:?}", file)),
+            (None, None) => None,
+        };
+        match (&described, amend) {
+            (Some(described), false) => {
+                info!("Committing in {:?} with {}", self.repo_path, described)
+            }
+            (Some(described), true) => info!(
+                "Amending last commit in {:?} with {}",
+                self.repo_path, described
+            ),
+            (None, true) => info!("Amending last commit in {:?}", self.repo_path),
+            (None, false) => anyhow::bail!("A commit message is required unless amending"),
+        }
+
+        if files.is_empty() && !options.allow_empty {
+            info!("No files to commit in {:?}", self.repo_path);
+            return Ok(());
+        }
+
+        // 1. Add specific files
+        if !files.is_empty() {
+            let relative_paths = self.relative_paths(files)?;
+            let mut args = vec!["add"];
+            args.extend(relative_paths.iter().map(|s| s.as_str()));
+
+            self.run(&args)?;
+        }
+
+        // Files that weren't modified in this repo leave nothing to commit; that's not an
+        // error. Rewording via --amend is still meaningful without staged changes, and an
+        // empty commit is what --allow-empty asks for. (Under dry-run nothing was staged,
+        // so show the commit anyway.)
+        let rewording = amend && described.is_some();
+        if !self.dry_run && !rewording && !options.allow_empty && !self.has_staged_changes()? {
+            info!("Nothing to commit in {:?}", self.repo_path);
+            return Ok(());
+        }
+
+        // 2. Commit
+        let mut args = vec!["commit"];
+        if amend {
+            args.push("--amend");
+        }
+        let message_file = message_file.map(|f| f.to_string_lossy());
+        match (message, &message_file) {
+            (Some(message), _) => args.extend(["-m", message]),
+            (None, Some(file)) => args.extend(["-F", file]),
+            (None, None) => args.push("--no-edit"),
+        }
+        if options.allow_empty {
+            args.push("--allow-empty");
+        }
+        if options.sign_off {
+            args.push("--signoff");
+        }
+        for trailer in &options.trailers {
+            args.extend(["--trailer", trailer]);
+        }
+        self.run(&args)
+    }
+
+    /// Convert paths to be relative to the repo root (repo_path), as git expects them.
+    /// Relative paths are taken from the current directory. A path outside the
+    /// repository is an error rather than something git would misread.
+    fn relative_paths(&self, files: &[PathBuf]) -> Result<Vec<String>> {
+        let repo = self
+            .repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| self.repo_path.clone());
+        let cwd = std::env::current_dir()?;
+
+        let mut relative_paths = Vec::new();
+        for file in files {
+            let abs_file = cwd.join(file);
+            // A file that doesn't exist (e.g. deleted) can't be canonicalized; its
+            // directory still can
+            let abs_file = match (abs_file.parent(), abs_file.file_name()) {
+                _ if abs_file.exists() => abs_file.canonicalize()?,
+                (Some(dir), Some(name)) if dir.exists() => dir.canonicalize()?.join(name),
+                _ => abs_file,
+            };
+            let rel = abs_file.strip_prefix(&repo).map_err(|_| {
+                anyhow::anyhow!("{:?} is outside the repository {:?}", file, self.repo_path)
+            })?;
+            relative_paths.push(rel.to_string_lossy().to_string());
+        }
+        Ok(relative_paths)
+    }
+
+    /// Content of `file` as of `rev`, or `None` if it didn't exist then.
+    pub fn file_at(&self, rev: &str, file: &Path) -> Result<Option<String>> {
+        let relative = self.relative_paths(&[file.to_path_buf()])?;
+        let object = format!("{}:{}", rev, relative[0]);
+        if !self
+            .command(&["cat-file", "-e", &object])
+            .output()?
+            .status
+            .success()
+        {
+            return Ok(None);
+        }
+
+        let output = self.command(&["show", &object]).output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to read {} in {:?}: {}",
+                object,
+                self.repo_path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(Some(String::from_utf8(output.stdout)?))
+    }
+
+    /// Drop the last commit, keeping uncommitted changes that don't conflict with it.
+    pub fn undo_last_commit(&self) -> Result<()> {
+        info!("Resetting {:?} to HEAD~1", self.repo_path);
+        self.run(&["reset", "--keep", "HEAD~1"])
+    }
+
+    /// Discard uncommitted changes to the tracked files among `files`, restoring them
+    /// from HEAD. Untracked files are left alone.
+    pub fn restore(&self, files: &[PathBuf]) -> Result<()> {
+        let relative_paths = self.relative_paths(files)?;
+        let mut args = vec!["ls-files", "--"];
+        args.extend(relative_paths.iter().map(|s| s.as_str()));
+        let output = self.command(&args).output()?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to list tracked files in {:?}", self.repo_path);
+        }
+
+        let tracked = String::from_utf8(output.stdout)?;
+        let tracked: Vec<&str> = tracked.lines().collect();
+        if tracked.is_empty() {
+            info!("Nothing to reset in {:?}", self.repo_path);
+            return Ok(());
+        }
+
+        info!("Resetting {} in {:?}", tracked.join(", "), self.repo_path);
+        let mut args = vec!["checkout", "HEAD", "--"];
+        args.extend(tracked);
+        self.run(&args)
+    }
+
+    fn has_staged_changes(&self) -> Result<bool> {
+        let status = self.command(&["diff", "--cached", "--quiet"]).status()?;
+        match status.code() {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => anyhow::bail!("Failed to inspect staged changes in {:?}", self.repo_path),
+        }
+    }
+
+    /// The tag name for `version`, e.g. `v1.2.3`.
+    pub fn tag_name(&self, version: &str) -> String {
+        format!("{}{}", self.tag_prefix, version)
+    }
+
+    /// Tag HEAD with `version`, as an annotated tag when a `message` is given. An
+    /// existing tag is only moved with `force`.
+    pub fn create_tag(&self, version: &str, force: bool, message: Option<&str>) -> Result<()> {
+        let tag_name = self.tag_name(version);
+        let mut args = vec!["tag"];
+        if let Some(message) = message {
+            // Keep changelog headings such as `### Added`, which git would strip as comments
+            args.extend(["-a", "--cleanup=whitespace", "-m", message]);
+        }
+        if self.tag_exists(&tag_name)? {
+            if !force {
+                anyhow::bail!("tag {} already exists; use --force to overwrite", tag_name);
+            }
+            info!("Moving tag '{}' in {:?}", tag_name, self.repo_path);
+            args.push("-f");
+        } else {
+            info!("Creating tag '{}' in {:?}", tag_name, self.repo_path);
+        }
+        args.push(&tag_name);
+        self.run(&args)
+    }
+
+    /// Whether anything under `path` differs from `tag`, counting uncommitted changes and
+    /// new untracked files. `None` when the tag doesn't exist.
+    pub fn changed_since(&self, tag: &str, path: &Path) -> Result<Option<bool>> {
+        if !self.tag_exists(tag)? {
+            return Ok(None);
+        }
+        let relative = self.relative_paths(&[path.to_path_buf()])?;
+        let mut relative = relative[0].as_str();
+        if relative.is_empty() {
+            relative = ".";
+        }
+        let output = self
+            .command(&["diff", "--quiet", tag, "--", relative])
+            .output()?;
+        match output.status.code() {
+            Some(0) => {}
+            Some(1) => return Ok(Some(true)),
+            _ => anyhow::bail!(
+                "Failed to compare {:?} with {}: {}",
+                path,
+                tag,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        }
+
+        // git diff doesn't see untracked files, such as a crate added since the tag
+        let output = self
+            .command(&["ls-files", "--others", "--exclude-standard", "--", relative])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to list untracked files in {:?}", path);
+        }
+        Ok(Some(!output.stdout.is_empty()))
+    }
+
+    fn tag_exists(&self, tag_name: &str) -> Result<bool> {
+        let refname = format!("refs/tags/{}", tag_name);
+        let output = self
+            .command(&["rev-parse", "-q", "--verify", &refname])
+            .output()?;
+        Ok(output.status.success())
+    }
+
+    pub fn push_tag(&self, version: &str) -> Result<()> {
+        let tag_name = self.tag_name(version);
+        info!("Pushing tag '{}' in {:?}", tag_name, self.repo_path);
+        self.run(&["push", &self.remote, &tag_name])
+    }
+
+    /// Tags made of the tag prefix and a semver version, in version order
+    /// (`v0.9.0` before `v0.10.0`). Other tags are ignored.
+    pub fn version_tags(&self) -> Result<Vec<(String, semver::Version)>> {
+        let pattern = format!("{}*", self.tag_prefix);
+        let output = self.command(&["tag", "--list", &pattern]).output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git tag --list failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let mut tags: Vec<(String, semver::Version)> = String::from_utf8(output.stdout)?
+            .lines()
+            .filter_map(|tag| {
+                let version = tag.strip_prefix(&self.tag_prefix)?;
+                Some((tag.to_string(), semver::Version::parse(version).ok()?))
+            })
+            .collect();
+        tags.sort_by(|a, b| a.1.cmp(&b.1));
+        Ok(tags)
+    }
+
+    pub fn remove_tag(&self, name: &str, remote: bool) -> Result<()> {
+        info!("Removing tag '{}' in {:?}", name, self.repo_path);
+        let _ = self.run(&["tag", "-d", name]);
+
+        if remote {
+            self.run(&["push", &self.remote, "--delete", name])?;
+        }
+        Ok(())
+    }
+
+    /// Extract (owner, repo) from the URL of the configured remote.
+    /// Supports both SSH (`git@github.com:owner/repo.git`) and HTTPS (`https://github.com/owner/repo.git`).
+    pub fn get_github_owner_repo(&self) -> Result<(String, String)> {
+        let output = self
+            .command(&["remote", "get-url", &self.remote])
+            .output()
+            .context("Failed to get remote URL")?;
+
+        if !output.status.success() {
+            anyhow::bail!("Failed to get remote URL for {:?}", self.repo_path);
+        }
+
+        let url = String::from_utf8(output.stdout)?.trim().to_string();
+        parse_github_url(&url)
+            .with_context(|| format!("Could not parse GitHub owner/repo from URL: {}", url))
+    }
+
+    /// Build a git invocation in the repository, echoing it when running verbosely.
+    fn command(&self, args: &[&str]) -> Command {
+        verbose!(
+            "$ git {}  (in {})",
+            format_args_for_display(args),
+            self.repo_path.display()
+        );
+        let mut command = Command::new("git");
+        command.current_dir(&self.repo_path).args(args);
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Result<()> {
+        if self.dry_run {
+            info!(
+                "[dry-run] git {}  (in {})",
+                format_args_for_display(args),
+                self.repo_path.display()
+            );
+            return Ok(());
+        }
+
+        // Network commands are retried with backoff while their errors look transient
+        let retries = if is_network_command(args) {
+            self.retry.retries
+        } else {
+            0
+        };
+        let mut delay = self.retry.delay;
+        for attempt in 1.. {
+            let output = self.execute(args)?;
+            if output.status.success() {
+                return Ok(());
+            }
+
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            if attempt > retries || !is_transient_failure(&stderr) {
+                if stderr.is_empty() {
+                    anyhow::bail!("Git command failed: {:?}", args);
+                }
+                anyhow::bail!("Git command failed: {:?}: {}", args, stderr);
+            }
+
+            warn!(
+                "Warning: git {} failed in {} ({}); retrying in {:?} ({}/{})",
+                args[0],
+                self.repo_path.display(),
+                stderr.lines().last().unwrap_or_default().trim(),
+                delay,
+                attempt,
+                retries
+            );
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+        unreachable!()
+    }
+
+    /// Run git with its stderr captured so failures can carry git's own explanation.
+    /// Git's output is only shown with `--verbose`, as log messages so it stays out of
+    /// our stdout.
+    fn execute(&self, args: &[&str]) -> Result<Output> {
+        let verbose = crate::log::enabled(crate::log::Level::Verbose);
+        let stdout = if verbose {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let child = self
+            .command(args)
+            .stdout(stdout)
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(format!("Failed to execute git {:?}", args))?;
+        let output = match self.timeout {
+            None => child
+                .wait_with_output()
+                .context(format!("Failed to execute git {:?}", args))?,
+            Some(timeout) => wait_with_timeout(child, timeout)
+                .context(format!("Failed to execute git {:?}", args))?
+                .with_context(|| {
+                    format!(
+                        "git {} timed out after {:?} in {} (is it waiting for credentials?)",
+                        format_args_for_display(args),
+                        timeout,
+                        self.repo_path.display()
+                    )
+                })?,
+        };
+
+        if verbose {
+            for stream in [&output.stdout, &output.stderr] {
+                let text = String::from_utf8_lossy(stream);
+                if !text.trim().is_empty() {
+                    verbose!("{}", text.trim_end());
+                }
+            }
+        }
+        Ok(output)
     }
-    Ok(())
 }
 
-/// Extract (owner, repo) from the git remote "origin" URL.
-/// Supports both SSH (`git@github.com:owner/repo.git`) and HTTPS (`https://github.com/owner/repo.git`).
-pub fn get_github_owner_repo(repo_path: &Path) -> Result<(String, String)> {
-    let output = Command::new("git")
-        .current_dir(repo_path)
-        .args(["remote", "get-url", "origin"])
-        .output()
-        .context("Failed to get remote URL")?;
+/// Wait for `child` for at most `timeout`, killing it once the deadline passes. Returns
+/// `None` when it had to be killed. Piped stdout and stderr are collected.
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> std::io::Result<Option<Output>> {
+    // Drain the pipes on the side so a chatty git can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
 
-    if !output.status.success() {
-        anyhow::bail!("Failed to get remote URL for {:?}", repo_path);
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(Output {
+                status,
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+            }));
+        }
+        if Instant::now() >= deadline {
+            // A helper spawned by git may keep the pipes open, so the readers are left behind
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
     }
+}
+
+/// Read `pipe` to the end on another thread.
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
 
-    let url = String::from_utf8(output.stdout)?.trim().to_string();
-    parse_github_url(&url)
-        .with_context(|| format!("Could not parse GitHub owner/repo from URL: {}", url))
+/// Commands that talk to a remote and may fail because of the network.
+fn is_network_command(args: &[&str]) -> bool {
+    matches!(args.first(), Some(&("push" | "pull" | "fetch")))
+}
+
+/// Whether git's error output points at a network hiccup rather than a real problem
+/// (rejected push, missing remote, authentication, ...).
+fn is_transient_failure(stderr: &str) -> bool {
+    const TRANSIENT: &[&str] = &[
+        "could not resolve host",
+        "connection timed out",
+        "operation timed out",
+        "connection reset",
+        "connection refused",
+        "temporary failure",
+        "the remote end hung up unexpectedly",
+        "early eof",
+        "rpc failed",
+        "502 bad gateway",
+        "503 service unavailable",
+    ];
+    let stderr = stderr.to_lowercase();
+    TRANSIENT.iter().any(|pattern| stderr.contains(pattern))
+}
+
+/// Render arguments the way they'd be typed in a shell, quoting where needed.
+fn format_args_for_display(args: &[&str]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) || arg.contains('\'') {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            } else {
+                arg.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 fn parse_github_url(url: &str) -> Result<(String, String)> {
@@ -226,21 +1126,910 @@ fn parse_github_url(url: &str) -> Result<(String, String)> {
 }
 
 pub fn execute_command(work_dir: &Path, command: &str) -> Result<()> {
-    let status = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .current_dir(work_dir)
-            .args(&["/C", command])
-            .status()
+    execute_command_with_env(work_dir, command, &[])
+}
+
+/// Run `command` through the platform shell in `work_dir` with extra environment variables.
+pub fn execute_command_with_env(
+    work_dir: &Path,
+    command: &str,
+    env: &[(&str, String)],
+) -> Result<()> {
+    let mut shell = if cfg!(target_os = "windows") {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
     } else {
-        Command::new("sh")
-            .current_dir(work_dir)
-            .args(&["-c", command])
-            .status()
-    }
-    .context(format!("Failed to execute command: {}", command))?;
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    let status = shell
+        .current_dir(work_dir)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .status()
+        .context(format!("Failed to execute command: {}", command))?;
 
     if !status.success() {
         anyhow::bail!("Command failed with status: {:?}", status);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::scoped::{CaptureLogger, with_logger};
+    use crate::log::{Level, Verbosity};
+    use std::sync::Arc;
+
+    /// A new repository at `dir` on branch `main`, with a committer configured.
+    fn init_repo(dir: &Path) -> Result<GitContext> {
+        let git = GitContext::new(dir);
+        git.run(&["init", "-q", "-b", "main"])?;
+        git.run(&["config", "user.email", "you@example.com"])?;
+        git.run(&["config", "user.name", "Your Name"])?;
+        Ok(git)
+    }
+
+    #[test]
+    fn test_verbose_echoes_git_commands() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        let git = GitContext::new(root);
+        let logger = Arc::new(CaptureLogger::default());
+        with_logger(Verbosity::Verbose, logger.clone(), || {
+            git.run(&["init", "-q"])?;
+            git.run(&["config", "user.name", "Your Name"])?;
+            git.run(&["config", "--get", "user.name"])
+        })?;
+
+        let echoed: Vec<String> = logger
+            .messages()
+            .into_iter()
+            .filter(|(level, _)| *level == Level::Verbose)
+            .map(|(_, message)| message)
+            .collect();
+        assert_eq!(
+            echoed,
+            vec![
+                format!("$ git init -q  (in {})", root.display()),
+                format!(
+                    "$ git config user.name 'Your Name'  (in {})",
+                    root.display()
+                ),
+                format!("$ git config --get user.name  (in {})", root.display()),
+                // git's stdout is logged rather than printed
+                "Your Name".to_string(),
+            ]
+        );
+
+        // Nothing is echoed at the default level
+        let logger = Arc::new(CaptureLogger::default());
+        with_logger(Verbosity::Normal, logger.clone(), || {
+            git.run(&["status", "--short"])
+        })?;
+        assert!(logger.messages().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_paths() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("repo/crate"))?;
+        std::fs::write(root.join("repo/crate/Cargo.toml"), "")?;
+        std::fs::write(root.join("Cargo.toml"), "")?;
+
+        let git = GitContext::new(&root.join("repo"));
+        assert_eq!(
+            git.relative_paths(&[
+                root.join("repo/crate/Cargo.toml"),
+                root.join("repo/crate/../crate/deleted.toml"),
+            ])?,
+            ["crate/Cargo.toml", "crate/deleted.toml"]
+        );
+
+        let outside = root.join("Cargo.toml");
+        let err = git
+            .relative_paths(std::slice::from_ref(&outside))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{:?} is outside the repository {:?}",
+                outside,
+                root.join("repo")
+            )
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_group_members_follows_symlinks() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+
+        for repo in ["repo_a", "repo_b"] {
+            std::fs::create_dir_all(root.join(repo).join("crate"))?;
+            GitContext::new(&root.join(repo)).run(&["init", "-q"])?;
+        }
+        std::os::unix::fs::symlink(root.join("repo_b/crate"), root.join("repo_a/linked"))?;
+        std::fs::create_dir(root.join("loose"))?;
+
+        let logger = Arc::new(CaptureLogger::default());
+        let repo_map = with_logger(Verbosity::Normal, logger.clone(), || {
+            group_members_by_repo(
+                &[
+                    root.join("repo_a/crate"),
+                    root.join("repo_a/linked"),
+                    root.join("loose"),
+                ],
+                Some(&root),
+            )
+        })?;
+
+        assert_eq!(repo_map.len(), 2);
+        assert_eq!(
+            repo_map[&root.join("repo_a")],
+            vec![root.join("repo_a/crate")]
+        );
+        // The symlinked member belongs to the repo it points into
+        assert_eq!(
+            repo_map[&root.join("repo_b")],
+            vec![root.join("repo_b/crate")]
+        );
+
+        let warnings: Vec<String> = logger
+            .messages()
+            .into_iter()
+            .filter(|(level, _)| *level == Level::Warn)
+            .map(|(_, message)| message)
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("loose"), "{}", warnings[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_members_is_sorted() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+
+        for repo in ["zeta", "alpha", "mid"] {
+            for member in ["b", "a"] {
+                std::fs::create_dir_all(root.join(repo).join(member))?;
+            }
+            GitContext::new(&root.join(repo)).run(&["init", "-q"])?;
+        }
+
+        let members: Vec<PathBuf> = ["zeta/b", "mid/a", "alpha/b", "zeta/a", "alpha/a", "mid/b"]
+            .iter()
+            .map(|m| root.join(m))
+            .collect();
+        let repo_map = group_members_by_repo(&members, Some(&root))?;
+
+        let order: Vec<(PathBuf, Vec<PathBuf>)> = repo_map.into_iter().collect();
+        let expected: Vec<(PathBuf, Vec<PathBuf>)> = ["alpha", "mid", "zeta"]
+            .iter()
+            .map(|repo| {
+                let repo = root.join(repo);
+                (repo.clone(), vec![repo.join("a"), repo.join("b")])
+            })
+            .collect();
+        assert_eq!(order, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_git_root_stops_at_boundary() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let outer = temp_dir.path().canonicalize()?;
+        let workspace = outer.join("workspace");
+        let member = workspace.join("member");
+        std::fs::create_dir_all(&member)?;
+        GitContext::new(&outer).run(&["init", "-q"])?;
+
+        // Unbounded, the member is attributed to the enclosing repo
+        assert_eq!(find_git_root(&member, None)?, Some(outer.clone()));
+        assert_eq!(find_git_root(&member, Some(&workspace))?, None);
+
+        // A repo at the boundary itself still counts
+        GitContext::new(&workspace).run(&["init", "-q"])?;
+        assert_eq!(
+            find_git_root(&member, Some(&workspace))?,
+            Some(workspace.clone())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_members_in_linked_worktree() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+        let main_repo = root.join("main");
+        std::fs::create_dir_all(main_repo.join("crate"))?;
+        std::fs::write(
+            main_repo.join("crate/Cargo.toml"),
+            "[package]\nname = \"a\"\n",
+        )?;
+
+        let git = init_repo(&main_repo)?;
+        git.run(&["add", "."])?;
+        git.run(&["commit", "-q", "-m", "Initial"])?;
+        git.run(&["worktree", "add", "-q", "-b", "wt", "../wt"])?;
+
+        let worktree = root.join("wt");
+        assert!(worktree.join(".git").is_file());
+
+        let repo_map = group_members_by_repo(&[worktree.join("crate")], Some(&root))?;
+        assert_eq!(repo_map.len(), 1);
+        assert_eq!(repo_map[&worktree], vec![worktree.join("crate")]);
+
+        // Git commands run from the worktree root see the worktree's branch
+        let wt_git = GitContext::new(&worktree);
+        wt_git.run(&["status", "--short"])?;
+        assert_eq!(wt_git.current_branch()?, "wt");
+
+        // A dangling pointer is reported instead of being treated as a repo
+        std::fs::write(worktree.join(".git"), "gitdir: /nonexistent/worktrees/wt\n")?;
+        let err = group_members_by_repo(&[worktree.join("crate")], Some(&root)).unwrap_err();
+        assert!(err.to_string().contains("missing git directory"), "{}", err);
+
+        Ok(())
+    }
+
+    impl GitContext {
+        fn current_branch_head(&self) -> Result<String> {
+            let output = self.command(&["rev-parse", "HEAD"]).output()?;
+            Ok(String::from_utf8(output.stdout)?.trim().to_string())
+        }
+    }
+
+    #[test]
+    fn test_version_tags() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let git = init_repo(temp_dir.path())?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        for tag in [
+            "v0.10.0",
+            "v0.9.0",
+            "v1.0.0-rc.1",
+            "vnext",
+            "release-2.0.0",
+            "v0.9.1",
+        ] {
+            git.run(&["tag", tag])?;
+        }
+
+        let names = |git: &GitContext| -> Result<Vec<String>> {
+            Ok(git
+                .version_tags()?
+                .into_iter()
+                .map(|(tag, _)| tag)
+                .collect())
+        };
+        assert_eq!(
+            names(&git)?,
+            vec!["v0.9.0", "v0.9.1", "v0.10.0", "v1.0.0-rc.1"]
+        );
+
+        let git = git.with_tag_prefix("release-");
+        git.create_tag("2.1.0", false, None)?;
+        assert_eq!(names(&git)?, vec!["release-2.0.0", "release-2.1.0"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_existing_tag() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let git = init_repo(temp_dir.path())?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        git.create_tag("0.1.0", false, None)?;
+        let first = git.current_branch_head()?;
+
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Second"])?;
+        let err = git.create_tag("0.1.0", false, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "tag v0.1.0 already exists; use --force to overwrite"
+        );
+        let tagged = |git: &GitContext| -> Result<String> {
+            let output = git.command(&["rev-parse", "v0.1.0^{commit}"]).output()?;
+            Ok(String::from_utf8(output.stdout)?.trim().to_string())
+        };
+        assert_eq!(tagged(&git)?, first);
+
+        git.create_tag("0.1.0", true, None)?;
+        assert_eq!(tagged(&git)?, git.current_branch_head()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeout() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let git = GitContext::new(temp_dir.path());
+        git.run(&["init", "-q"])?;
+        git.run(&["config", "alias.hang", "!sleep 5"])?;
+
+        let git = git.with_timeout(Some(Duration::from_millis(200)));
+        let started = Instant::now();
+        let err = git.run(&["hang"]).unwrap_err().to_string();
+        assert!(
+            started.elapsed() < Duration::from_secs(4),
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(
+            err.starts_with("git hang timed out after 200ms in "),
+            "{}",
+            err
+        );
+
+        // Commands that finish in time are unaffected
+        git.run(&["status", "--short"])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_annotated_tag() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let git = init_repo(temp_dir.path())?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+
+        git.create_tag("0.2.0", false, Some("### Added\n\n- Parallel loading"))?;
+        let output = git
+            .command(&[
+                "tag",
+                "-l",
+                "--format=%(objecttype)%0a%(contents)",
+                "v0.2.0",
+            ])
+            .output()?;
+        assert_eq!(
+            String::from_utf8(output.stdout)?,
+            "tag\n### Added\n\n- Parallel loading\n\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_only_transient_network_failures() -> Result<()> {
+        assert!(is_network_command(&["fetch", "origin"]));
+        assert!(is_network_command(&["push", "-u", "origin", "main"]));
+        assert!(!is_network_command(&["commit", "-m", "push"]));
+
+        assert!(is_transient_failure(
+            "fatal: unable to access 'https://github.com/org/repo.git/': Could not resolve host: github.com"
+        ));
+        assert!(is_transient_failure(
+            "fatal: the remote end hung up unexpectedly"
+        ));
+        assert!(!is_transient_failure(
+            "! [rejected]        main -> main (non-fast-forward)"
+        ));
+        assert!(!is_transient_failure(
+            "fatal: 'nowhere' does not appear to be a git repository"
+        ));
+
+        // A permanent failure gives up right away instead of waiting out the retries
+        let temp_dir = tempfile::tempdir()?;
+        let git = GitContext::new(temp_dir.path())
+            .with_remote("nowhere")
+            .with_retry(RetryPolicy {
+                retries: 3,
+                delay: Duration::from_secs(60),
+            });
+        git.run(&["init", "-q"])?;
+
+        let logger = Arc::new(CaptureLogger::default());
+        let result = with_logger(Verbosity::Normal, logger.clone(), || git.fetch());
+        assert!(result.is_err());
+        assert!(
+            logger
+                .messages()
+                .iter()
+                .all(|(level, _)| *level != Level::Warn)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failure_carries_git_stderr() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let git = GitContext::new(temp_dir.path());
+        git.run(&["init", "-q"])?;
+
+        let err = git.checkout_branch("no-such-branch").unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.starts_with(r#"Git command failed: ["checkout", "no-such-branch"]: "#),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("no-such-branch' did not match"),
+            "{}",
+            message
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_branch() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let upstream = temp_dir.path().join("upstream");
+        let clone = temp_dir.path().join("clone");
+        std::fs::create_dir(&upstream)?;
+
+        let git = GitContext::new(&upstream);
+        git.run(&["init", "-q", "-b", "trunk"])?;
+        assert!(git.default_branch().is_err());
+
+        git.run(&["config", "user.email", "test@example.com"])?;
+        git.run(&["config", "user.name", "Test User"])?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        git.run(&["branch", "master"])?;
+        assert_eq!(git.default_branch()?, "master");
+        git.run(&["branch", "main"])?;
+        assert_eq!(git.default_branch()?, "main");
+
+        GitContext::new(temp_dir.path()).run(&[
+            "clone",
+            "-q",
+            upstream.to_str().unwrap(),
+            clone.to_str().unwrap(),
+        ])?;
+        let cloned = GitContext::new(&clone);
+        cloned.run(&["checkout", "-q", "-b", "feature"])?;
+        assert_eq!(cloned.default_branch()?, "trunk");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_branches() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let upstream = temp_dir.path().join("upstream");
+        let clone = temp_dir.path().join("clone");
+        std::fs::create_dir(&upstream)?;
+
+        let git = init_repo(&upstream)?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        git.run(&["branch", "release-0.2"])?;
+        GitContext::new(temp_dir.path()).run(&[
+            "clone",
+            "-q",
+            upstream.to_str().unwrap(),
+            clone.to_str().unwrap(),
+        ])?;
+
+        let cloned = GitContext::new(&clone);
+        cloned.run(&["config", "user.email", "test@example.com"])?;
+        cloned.run(&["config", "user.name", "Test User"])?;
+        for branch in ["release-0.2", "release-0.1", "keep", "wip"] {
+            cloned.run(&["branch", branch])?;
+        }
+        cloned.run(&["checkout", "-q", "-b", "unmerged"])?;
+        cloned.run(&["commit", "-q", "--allow-empty", "-m", "Not merged"])?;
+        cloned.run(&["checkout", "-q", "wip"])?;
+
+        let merged = cloned.merged_branches(&["keep".to_string()])?;
+        assert_eq!(merged, ["release-0.1", "release-0.2"]);
+
+        for branch in &merged {
+            cloned.delete_merged_branch(branch, true)?;
+        }
+        assert!(cloned.merged_branches(&["keep".to_string()])?.is_empty());
+        assert!(!git.branch_exists("release-0.2")?);
+        assert!(cloned.branch_exists("unmerged")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_upstream() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = GitContext::new(temp_dir.path());
+        for remote in ["origin.git", "fork.git"] {
+            root.run(&["init", "-q", "--bare", "-b", "main", remote])?;
+        }
+        let clone = temp_dir.path().join("clone");
+        let origin = temp_dir.path().join("origin.git");
+        let fork = temp_dir.path().join("fork.git");
+        root.run(&[
+            "clone",
+            "-q",
+            origin.to_str().unwrap(),
+            clone.to_str().unwrap(),
+        ])?;
+
+        let git = GitContext::new(&clone);
+        git.run(&["config", "user.email", "test@example.com"])?;
+        git.run(&["config", "user.name", "Test User"])?;
+        git.run(&["remote", "add", "fork", fork.to_str().unwrap()])?;
+        git.run(&["checkout", "-q", "-b", "main"])?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        let head_of = |remote: &Path, branch: &str| -> Result<Option<String>> {
+            let output = GitContext::new(remote)
+                .command(&["rev-parse", "--verify", "--quiet", branch])
+                .output()?;
+            Ok(output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+        };
+
+        // A fresh branch goes to the configured remote and tracks it
+        git.push(false)?;
+        assert_eq!(git.upstream()?.as_deref(), Some("origin/main"));
+        assert_eq!(head_of(&origin, "main")?, Some(git.current_branch_head()?));
+
+        // An existing upstream on another remote is respected
+        git.run(&["checkout", "-q", "-b", "feature"])?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Feature"])?;
+        git.run(&["push", "-q", "-u", "fork", "feature"])?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "More"])?;
+        git.push(false)?;
+        assert_eq!(head_of(&fork, "feature")?, Some(git.current_branch_head()?));
+        assert_eq!(head_of(&origin, "feature")?, None);
+
+        // Rewritten history needs --force-with-lease
+        git.run(&["commit", "-q", "--amend", "--allow-empty", "-m", "Reworded"])?;
+        assert!(git.push(false).is_err());
+        git.push(true)?;
+        assert_eq!(head_of(&fork, "feature")?, Some(git.current_branch_head()?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_submodules() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let init = |dir: &Path| -> Result<GitContext> {
+            std::fs::create_dir(dir)?;
+            let git = init_repo(dir)?;
+            git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+            Ok(git)
+        };
+        let shared = init(&temp_dir.path().join("shared"))?;
+        let app = init(&temp_dir.path().join("app"))?;
+        let allow_file = ["-c", "protocol.file.allow=always"];
+
+        // Repositories without submodules are skipped
+        app.update_submodules()?;
+
+        let url = shared.repo_path().to_str().unwrap();
+        app.run(&[&allow_file[..], &["submodule", "add", "-q", url, "shared"]].concat())?;
+        app.run(&["commit", "-q", "-m", "Add shared"])?;
+        let pinned = shared.current_branch_head()?;
+
+        // A feature branch moves the submodule to a newer commit
+        shared.run(&["commit", "-q", "--allow-empty", "-m", "Newer"])?;
+        let submodule = GitContext::new(&app.repo_path().join("shared"));
+        submodule.run(&[&allow_file[..], &["pull", "-q", "origin", "main"]].concat())?;
+        app.run(&["checkout", "-q", "-b", "feature"])?;
+        app.run(&["commit", "-q", "-am", "Bump shared"])?;
+
+        // Switching back leaves the submodule stale until it is updated
+        app.run(&["checkout", "-q", "main"])?;
+        assert_ne!(submodule.current_branch_head()?, pinned);
+        app.update_submodules()?;
+        assert_eq!(submodule.current_branch_head()?, pinned);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_track_branch() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let upstream = temp_dir.path().join("upstream");
+        let clone = temp_dir.path().join("clone");
+        std::fs::create_dir(&upstream)?;
+
+        let git = init_repo(&upstream)?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        git.run(&["branch", "release-0.3"])?;
+        GitContext::new(temp_dir.path()).run(&[
+            "clone",
+            "-q",
+            upstream.to_str().unwrap(),
+            clone.to_str().unwrap(),
+        ])?;
+
+        let cloned = GitContext::new(&clone);
+        let upstream_of = |branch: &str| -> Result<String> {
+            let output = cloned
+                .command(&["rev-parse", "--abbrev-ref", &format!("{}@{{u}}", branch)])
+                .output()?;
+            Ok(String::from_utf8(output.stdout)?.trim().to_string())
+        };
+
+        cloned.run(&["checkout", "-q", "-b", "release-0.3"])?;
+        assert_eq!(upstream_of("release-0.3")?, "");
+        cloned.track_branch("release-0.3")?;
+        assert_eq!(upstream_of("release-0.3")?, "origin/release-0.3");
+
+        // Not on the remote yet: nothing to track, and not an error
+        cloned.run(&["checkout", "-q", "-b", "release-0.4"])?;
+        cloned.track_branch("release-0.4")?;
+        assert_eq!(upstream_of("release-0.4")?, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_checked_out_branch() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let git = init_repo(temp_dir.path())?;
+        git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        git.run(&["checkout", "-q", "-b", "feature"])?;
+
+        let logger = Arc::new(CaptureLogger::default());
+        with_logger(Verbosity::Normal, logger.clone(), || {
+            git.remove_branch("feature", false, None)
+        })?;
+        assert!(git.branch_exists("feature")?);
+        assert!(
+            logger
+                .messages()
+                .iter()
+                .any(|(level, msg)| *level == Level::Warn
+                    && msg.contains("'feature' is checked out"))
+        );
+
+        git.remove_branch("feature", false, Some("main"))?;
+        assert!(!git.branch_exists("feature")?);
+        assert_eq!(git.current_branch()?, "main");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ahead_behind_and_ff_only() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let remote = temp_dir.path().join("remote.git");
+        let local = temp_dir.path().join("local");
+        let other = temp_dir.path().join("other");
+
+        GitContext::new(temp_dir.path()).run(&[
+            "init",
+            "-q",
+            "--bare",
+            "-b",
+            "main",
+            "remote.git",
+        ])?;
+        for clone in [&local, &other] {
+            GitContext::new(temp_dir.path()).run(&[
+                "clone",
+                "-q",
+                remote.to_str().unwrap(),
+                clone.to_str().unwrap(),
+            ])?;
+            let git = GitContext::new(clone);
+            git.run(&["config", "user.email", "you@example.com"])?;
+            git.run(&["config", "user.name", "Your Name"])?;
+        }
+        let local_git = GitContext::new(&local);
+        let other_git = GitContext::new(&other);
+
+        local_git.run(&["commit", "-q", "--allow-empty", "-m", "Initial"])?;
+        // No upstream yet
+        assert_eq!(local_git.ahead_behind()?, None);
+        local_git.run(&["push", "-q", "-u", "origin", "main"])?;
+        assert_eq!(local_git.ahead_behind()?, Some((0, 0)));
+
+        other_git.run(&["pull", "-q", "origin", "main"])?;
+        other_git.run(&["commit", "-q", "--allow-empty", "-m", "Theirs"])?;
+        other_git.run(&["push", "-q", "origin", "main"])?;
+        for message in ["One", "Two", "Three"] {
+            local_git.run(&["commit", "-q", "--allow-empty", "-m", message])?;
+        }
+        local_git.run(&["fetch", "-q"])?;
+        assert_eq!(local_git.ahead_behind()?, Some((3, 1)));
+
+        // Diverged: reported and left alone
+        let head = local_git.current_branch_head()?;
+        let err = local_git.ff_only().unwrap_err();
+        assert!(err.to_string().contains("3 ahead, 1 behind"), "{}", err);
+        assert_eq!(local_git.current_branch_head()?, head);
+
+        // Only behind: fast-forwarded
+        other_git.fetch()?;
+        assert_eq!(other_git.ahead_behind()?, Some((0, 0)));
+        local_git.run(&["reset", "-q", "--hard", "@{u}"])?;
+        other_git.run(&["commit", "-q", "--allow-empty", "-m", "More"])?;
+        other_git.run(&["push", "-q", "origin", "main"])?;
+        local_git.fetch()?;
+        local_git.ff_only()?;
+        assert_eq!(local_git.ahead_behind()?, Some((0, 0)));
+        assert_eq!(
+            local_git.current_branch_head()?,
+            other_git.current_branch_head()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        let git = init_repo(root)?;
+        std::fs::write(root.join("a.txt"), "one\n")?;
+        std::fs::write(root.join("b.txt"), "one\n")?;
+        git.run(&["add", "."])?;
+        git.run(&["commit", "-q", "-m", "Initial"])?;
+
+        assert_eq!(git.diff(false, false)?, "");
+
+        std::fs::write(root.join("a.txt"), "two\n")?;
+        std::fs::write(root.join("b.txt"), "two\n")?;
+        git.run(&["add", "b.txt"])?;
+
+        let unstaged = git.diff(false, false)?;
+        assert!(
+            unstaged.contains("+two") && unstaged.contains("a.txt"),
+            "{}",
+            unstaged
+        );
+        assert!(!unstaged.contains("b.txt"), "{}", unstaged);
+
+        let staged = git.diff(true, true)?;
+        assert!(staged.contains("b.txt | 2 +-"), "{}", staged);
+        assert!(!staged.contains("a.txt"), "{}", staged);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+        let git = init_repo(&root)?;
+        std::fs::create_dir(root.join("a"))?;
+        std::fs::write(root.join("a/Cargo.toml"), "version = \"0.1.0\"\n")?;
+        std::fs::write(root.join("notes.txt"), "keep\n")?;
+        git.run(&["add", "."])?;
+        git.run(&["commit", "-q", "-m", "Initial"])?;
+
+        std::fs::write(root.join("a/Cargo.toml"), "version = \"9.9.9\"\n")?;
+        std::fs::write(root.join("notes.txt"), "edited\n")?;
+        std::fs::create_dir(root.join("b"))?;
+        std::fs::write(root.join("b/Cargo.toml"), "untracked\n")?;
+        std::fs::write(root.join("a/Cargo.lock"), "")?;
+
+        git.restore(&[root.join("a/Cargo.toml"), root.join("b/Cargo.toml")])?;
+
+        assert_eq!(
+            std::fs::read_to_string(root.join("a/Cargo.toml"))?,
+            "version = \"0.1.0\"\n"
+        );
+        // Only the given, tracked files are touched
+        assert_eq!(std::fs::read_to_string(root.join("notes.txt"))?, "edited\n");
+        assert_eq!(
+            std::fs::read_to_string(root.join("b/Cargo.toml"))?,
+            "untracked\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_and_pop() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        let git = init_repo(root)?;
+        std::fs::write(root.join("a.txt"), "one\n")?;
+        git.run(&["add", "."])?;
+        git.run(&["commit", "-q", "-m", "Initial"])?;
+
+        let stash_count = || -> Result<usize> {
+            let output = git.command(&["stash", "list"]).output()?;
+            Ok(String::from_utf8(output.stdout)?.lines().count())
+        };
+
+        // Clean tree: nothing stashed, and pop has nothing to restore
+        git.stash(None, false)?;
+        assert_eq!(stash_count()?, 0);
+        git.stash_pop()?;
+
+        // Stashes made outside meta are left alone
+        std::fs::write(root.join("a.txt"), "mine\n")?;
+        git.run(&["stash", "push", "-q", "-m", "by hand"])?;
+
+        std::fs::write(root.join("a.txt"), "two\n")?;
+        std::fs::write(root.join("new.txt"), "new\n")?;
+        git.stash(Some("before switching"), true)?;
+        assert_eq!(std::fs::read_to_string(root.join("a.txt"))?, "one\n");
+        assert!(!root.join("new.txt").exists());
+        assert_eq!(stash_count()?, 2);
+
+        git.stash_pop()?;
+        assert_eq!(std::fs::read_to_string(root.join("a.txt"))?, "two\n");
+        assert!(root.join("new.txt").exists());
+        assert_eq!(stash_count()?, 1);
+
+        // The remaining stash isn't ours
+        git.run(&["checkout", "-q", "--", "."])?;
+        git.stash_pop()?;
+        assert_eq!(stash_count()?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dry_run_leaves_refs_untouched() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        let git = |args: &[&str]| -> Result<String> {
+            let output = Command::new("git").current_dir(root).args(args).output()?;
+            anyhow::ensure!(output.status.success(), "git {:?} failed", args);
+            Ok(String::from_utf8(output.stdout)?)
+        };
+        let ctx = init_repo(root)?;
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"a\"\n")?;
+        git(&["add", "."])?;
+        git(&["commit", "-m", "Initial"])?;
+        git(&["branch", "old"])?;
+        git(&["tag", "v0.1.0"])?;
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"b\"\n")?;
+
+        let refs_before = git(&["for-each-ref"])?;
+
+        let ctx = ctx.with_dry_run(true);
+        let logger = Arc::new(CaptureLogger::default());
+        with_logger(Verbosity::Normal, logger.clone(), || -> Result<()> {
+            ctx.create_branch("feature", Some("main"))?;
+            ctx.create_branch("old", None)?;
+            ctx.create_tag("1.0.0", false, None)?;
+            ctx.remove_branch("old", false, None)?;
+            ctx.remove_tag("v0.1.0", false)?;
+            ctx.commit(
+                Some("bump"),
+                &[root.join("Cargo.toml")],
+                &CommitOptions::default(),
+            )
+        })?;
+
+        assert_eq!(git(&["for-each-ref"])?, refs_before);
+        assert_eq!(git(&["rev-parse", "--abbrev-ref", "HEAD"])?.trim(), "main");
+        assert_eq!(git(&["status", "--porcelain"])?.trim(), "M Cargo.toml");
+
+        let planned: Vec<String> = logger
+            .messages()
+            .into_iter()
+            .filter_map(|(_, message)| message.strip_prefix("[dry-run] ").map(str::to_string))
+            .map(|message| message.split("  (in").next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            planned,
+            vec![
+                "git checkout -b feature main",
+                "git checkout old",
+                "git tag v1.0.0",
+                "git branch -D old",
+                "git tag -d v0.1.0",
+                "git add Cargo.toml",
+                "git commit -m bump",
+            ]
+        );
+
+        Ok(())
+    }
+}
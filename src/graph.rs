@@ -0,0 +1,278 @@
+use crate::editor::CrateEditor;
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+/// Internal dependency graph between workspace members, keyed by package name.
+pub struct DependencyGraph {
+    /// Each member mapped to the members it depends on.
+    deps: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    /// Every dependency between members, dev-dependencies included.
+    pub fn from_editors(editors: &[CrateEditor]) -> Self {
+        Self::with_edges(editors, CrateEditor::get_dependencies)
+    }
+
+    /// The dependencies that constrain build and publish order. Dev-dependencies are
+    /// left out since cargo allows cycles through them.
+    pub fn build_order(editors: &[CrateEditor]) -> Self {
+        Self::with_edges(editors, CrateEditor::get_build_order_dependencies)
+    }
+
+    fn with_edges<F>(editors: &[CrateEditor], edges_of: F) -> Self
+    where
+        F: Fn(&CrateEditor, &HashSet<String>) -> Vec<String>,
+    {
+        let members: HashSet<String> = editors
+            .iter()
+            .filter_map(|e| e.get_package_name())
+            .collect();
+
+        let mut deps = BTreeMap::new();
+        for editor in editors {
+            let Some(name) = editor.get_package_name() else {
+                continue;
+            };
+            let edges: BTreeSet<String> = edges_of(editor, &members)
+                .into_iter()
+                .filter(|dep| *dep != name)
+                .collect();
+            deps.entry(name).or_insert_with(BTreeSet::new).extend(edges);
+        }
+
+        Self { deps }
+    }
+
+    /// Members ordered so that every crate comes after all of its dependencies.
+    /// Ties are broken alphabetically so the order is stable across runs.
+    pub fn topo_order(&self) -> Result<Vec<String>> {
+        let mut remaining: BTreeMap<&str, usize> = self
+            .deps
+            .iter()
+            .map(|(name, deps)| (name.as_str(), deps.len()))
+            .collect();
+        let mut ready: BTreeSet<&str> = remaining
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(name, _)| *name)
+            .collect();
+
+        let mut order = Vec::with_capacity(self.deps.len());
+        while let Some(name) = ready.pop_first() {
+            remaining.remove(name);
+            order.push(name.to_string());
+
+            for (dependent, deps) in &self.deps {
+                if deps.contains(name)
+                    && let Some(count) = remaining.get_mut(dependent.as_str())
+                {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(dependent);
+                    }
+                }
+            }
+        }
+
+        if !remaining.is_empty() {
+            let cycle = self.find_cycle(&remaining.keys().copied().collect());
+            anyhow::bail!("dependency cycle detected: {}", cycle.join(" -> "));
+        }
+        Ok(order)
+    }
+
+    /// Each member mapped to the members it depends on.
+    pub fn dependencies(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.deps
+    }
+
+    /// Graphviz DOT, with an edge from each crate to each of its dependencies.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph workspace {\n");
+        for name in self.deps.keys() {
+            writeln!(out, "    {:?};", name).unwrap();
+        }
+        for (name, deps) in &self.deps {
+            for dep in deps {
+                writeln!(out, "    {:?} -> {:?};", name, dep).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// A Mermaid flowchart. Nodes get generated ids since crate names may contain
+    /// characters Mermaid doesn't accept in ids.
+    pub fn to_mermaid(&self) -> String {
+        let ids: BTreeMap<&str, String> = self
+            .deps
+            .keys()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), format!("n{}", i)))
+            .collect();
+
+        let mut out = String::from("graph TD\n");
+        for (name, id) in &ids {
+            writeln!(out, "    {}[\"{}\"]", id, name).unwrap();
+        }
+        for (name, deps) in &self.deps {
+            for dep in deps {
+                writeln!(out, "    {} --> {}", ids[name.as_str()], ids[dep.as_str()]).unwrap();
+            }
+        }
+        out
+    }
+
+    /// A cycle among the nodes `topo_order` couldn't place, e.g. `[a, b, a]`.
+    ///
+    /// Every stuck node still waits on another stuck node, so following those edges
+    /// from any of them must eventually come back to a node already on the path.
+    fn find_cycle<'a>(&'a self, stuck: &BTreeSet<&'a str>) -> Vec<&'a str> {
+        let mut path: Vec<&str> = Vec::new();
+        let mut current = *stuck.first().expect("no stuck nodes");
+        loop {
+            if let Some(start) = path.iter().position(|n| *n == current) {
+                let mut cycle = path.split_off(start);
+                cycle.push(current);
+                return cycle;
+            }
+            path.push(current);
+            current = self.deps[current]
+                .iter()
+                .map(String::as_str)
+                .find(|dep| stuck.contains(dep))
+                .expect("stuck node without stuck dependency");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn write_crate(root: &Path, name: &str, deps: &[&str]) -> Result<CrateEditor> {
+        let dir = root.join(name);
+        fs::create_dir(&dir)?;
+        let mut manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[dependencies]\n",
+            name
+        );
+        for dep in deps {
+            manifest.push_str(&format!("{} = {{ path = \"../{}\" }}\n", dep, dep));
+        }
+        fs::write(dir.join("Cargo.toml"), manifest)?;
+        CrateEditor::new(&dir)
+    }
+
+    #[test]
+    fn test_topo_order_linear_chain() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        // c -> b -> a, declared in reverse
+        let editors = vec![
+            write_crate(root, "c", &["b"])?,
+            write_crate(root, "b", &["a"])?,
+            write_crate(root, "a", &[])?,
+        ];
+
+        let graph = DependencyGraph::build_order(&editors);
+        assert_eq!(graph.topo_order()?, vec!["a", "b", "c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_graph() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        let editors = vec![
+            write_crate(root, "app", &["crate-core", "utils"])?,
+            write_crate(root, "utils", &["crate-core"])?,
+            write_crate(root, "crate-core", &[])?,
+        ];
+        let graph = DependencyGraph::from_editors(&editors);
+
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph workspace {
+    "app";
+    "crate-core";
+    "utils";
+    "app" -> "crate-core";
+    "app" -> "utils";
+    "utils" -> "crate-core";
+}
+"#
+        );
+        assert_eq!(
+            graph.to_mermaid(),
+            r#"graph TD
+    n0["app"]
+    n1["crate-core"]
+    n2["utils"]
+    n0 --> n1
+    n0 --> n2
+    n2 --> n1
+"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_topo_order_rejects_cycles() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        let editors = vec![
+            write_crate(root, "crate_a", &["crate_b"])?,
+            write_crate(root, "crate_b", &["crate_a"])?,
+            // Depends on the cycle without being part of it
+            write_crate(root, "app", &["crate_a"])?,
+        ];
+
+        let graph = DependencyGraph::build_order(&editors);
+        assert_eq!(
+            graph.topo_order().unwrap_err().to_string(),
+            "dependency cycle detected: crate_a -> crate_b -> crate_a"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_order_ignores_dev_dependency_cycles() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+
+        // a -> b, while b only dev-depends on a
+        let mut editors = vec![
+            write_crate(root, "a", &["b"])?,
+            write_crate(root, "b", &[])?,
+        ];
+        fs::write(
+            root.join("b/Cargo.toml"),
+            "[package]\nname = \"b\"\nversion = \"0.1.0\"\n\n[dev-dependencies]\na = { path = \"../a\" }\n",
+        )?;
+        editors[1] = CrateEditor::new(&root.join("b"))?;
+
+        assert_eq!(
+            DependencyGraph::build_order(&editors).topo_order()?,
+            vec!["b", "a"]
+        );
+        // The full graph still shows the dev-dependency
+        assert!(
+            DependencyGraph::from_editors(&editors)
+                .topo_order()
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
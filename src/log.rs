@@ -0,0 +1,216 @@
+//! Leveled console output.
+//!
+//! Everything the tool prints about what it is doing goes through the `info!`,
+//! `verbose!`, `warn!` and `error!` macros so that `--quiet`/`--verbose` apply
+//! uniformly. Command results that are the point of a command (PR listings,
+//! generated files, ...) are still printed directly.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Verbose,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only errors
+    Quiet,
+    /// Warnings and per-repo summaries
+    Normal,
+    /// Everything, including each git command
+    Verbose,
+}
+
+impl Verbosity {
+    fn max_level(self) -> Level {
+        match self {
+            Verbosity::Quiet => Level::Error,
+            Verbosity::Normal => Level::Info,
+            Verbosity::Verbose => Level::Verbose,
+        }
+    }
+}
+
+/// Destination for log messages that passed the verbosity filter.
+pub trait Logger: Send + Sync {
+    fn log(&self, level: Level, message: &str);
+}
+
+/// Informational output goes to stdout. Warnings, errors and verbose diagnostics go
+/// to stderr so they never mix with a command's regular output.
+pub struct ConsoleLogger;
+
+impl Logger for ConsoleLogger {
+    fn log(&self, level: Level, message: &str) {
+        match level {
+            Level::Info if !INFO_TO_STDERR.load(Ordering::Relaxed) => println!("{}", message),
+            _ => eprintln!("{}", message),
+        }
+    }
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(1);
+static INFO_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send informational messages to stderr too, keeping stdout free for
+/// machine-readable output.
+pub fn set_info_to_stderr(enabled: bool) {
+    INFO_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    let value = match verbosity {
+        Verbosity::Quiet => 0,
+        Verbosity::Normal => 1,
+        Verbosity::Verbose => 2,
+    };
+    VERBOSITY.store(value, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    #[cfg(test)]
+    if let Some(verbosity) = scoped::verbosity() {
+        return verbosity;
+    }
+
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    level <= verbosity().max_level()
+}
+
+pub fn log(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    #[cfg(test)]
+    if scoped::log(level, &args.to_string()) {
+        return;
+    }
+
+    ConsoleLogger.log(level, &args.to_string());
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Error, format_args!($($arg)*)) };
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Warn, format_args!($($arg)*)) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Info, format_args!($($arg)*)) };
+}
+
+macro_rules! verbose {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Verbose, format_args!($($arg)*)) };
+}
+
+/// Per-thread logger overrides so tests can assert on emitted messages without
+/// racing each other through the global verbosity.
+#[cfg(test)]
+pub mod scoped {
+    use super::{Level, Logger, Verbosity};
+    use std::cell::RefCell;
+    use std::sync::{Arc, Mutex};
+
+    thread_local! {
+        static CURRENT: RefCell<Option<(Verbosity, Arc<dyn Logger>)>> = const { RefCell::new(None) };
+    }
+
+    /// Collects every message it receives.
+    #[derive(Default)]
+    pub struct CaptureLogger {
+        messages: Mutex<Vec<(Level, String)>>,
+    }
+
+    impl CaptureLogger {
+        pub fn messages(&self) -> Vec<(Level, String)> {
+            self.messages.lock().unwrap().clone()
+        }
+    }
+
+    impl Logger for CaptureLogger {
+        fn log(&self, level: Level, message: &str) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push((level, message.to_string()));
+        }
+    }
+
+    /// Run `f` with `logger` receiving this thread's messages at `verbosity`.
+    pub fn with_logger<R>(
+        verbosity: Verbosity,
+        logger: Arc<dyn Logger>,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let previous = CURRENT.with(|c| c.replace(Some((verbosity, logger))));
+        let result = f();
+        CURRENT.with(|c| *c.borrow_mut() = previous);
+        result
+    }
+
+    pub(super) fn verbosity() -> Option<Verbosity> {
+        CURRENT.with(|c| c.borrow().as_ref().map(|(v, _)| *v))
+    }
+
+    pub(super) fn log(level: Level, message: &str) -> bool {
+        CURRENT.with(|c| match c.borrow().as_ref() {
+            Some((_, logger)) => {
+                logger.log(level, message);
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scoped::{CaptureLogger, with_logger};
+    use super::*;
+    use std::sync::Arc;
+
+    fn emit_all() {
+        error!("an error");
+        warn!("a warning");
+        info!("some info");
+        verbose!("a detail");
+    }
+
+    #[test]
+    fn test_verbosity_filters_messages() {
+        let levels = |verbosity| {
+            let logger = Arc::new(CaptureLogger::default());
+            with_logger(verbosity, logger.clone(), emit_all);
+            logger
+                .messages()
+                .into_iter()
+                .map(|(level, _)| level)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(levels(Verbosity::Quiet), vec![Level::Error]);
+        assert_eq!(
+            levels(Verbosity::Normal),
+            vec![Level::Error, Level::Warn, Level::Info]
+        );
+        assert_eq!(
+            levels(Verbosity::Verbose),
+            vec![Level::Error, Level::Warn, Level::Info, Level::Verbose]
+        );
+    }
+}
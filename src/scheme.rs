@@ -0,0 +1,139 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How member versions are written and moved forward. Cargo only accepts semver in
+/// manifests, so every scheme produces a `semver::Version`; they differ in which
+/// versions they accept and in what `major`, `minor` and `patch` mean.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionScheme {
+    /// MAJOR.MINOR.PATCH
+    #[default]
+    Semver,
+    /// YEAR.MONTH.PATCH, e.g. 2024.3.0
+    Calver,
+}
+
+/// The part of a version an increment moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Part {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl VersionScheme {
+    /// Check `raw` against the scheme. Calendar versions may be written with a
+    /// zero-padded month (`2024.03.0`), which is normalized since cargo rejects it.
+    pub fn parse(self, raw: &str) -> Result<Version> {
+        match self {
+            Self::Semver => {
+                Version::parse(raw).map_err(|e| anyhow::anyhow!("Invalid version '{}': {}", raw, e))
+            }
+            Self::Calver => {
+                let invalid = || {
+                    anyhow::anyhow!(
+                        "Invalid calendar version '{}' (expected YEAR.MONTH.PATCH, e.g. 2024.3.0)",
+                        raw
+                    )
+                };
+                let parts: Vec<u64> = raw
+                    .split('.')
+                    .map(|part| {
+                        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                            return Err(invalid());
+                        }
+                        part.parse().map_err(|_| invalid())
+                    })
+                    .collect::<Result<_>>()?;
+                let [year, month, patch] = parts[..] else {
+                    return Err(invalid());
+                };
+                if !(1000..=9999).contains(&year) || !(1..=12).contains(&month) {
+                    return Err(invalid());
+                }
+                Ok(Version::new(year, month, patch))
+            }
+        }
+    }
+
+    /// Move `current` forward by `part`. For calendar versions `patch` adds a release
+    /// within the version's month, while `major` and `minor` start this month's first
+    /// release (or add one to it, if this month already had a release).
+    pub fn increment(self, current: &Version, part: Part) -> Version {
+        self.increment_on(current, part, current_month())
+    }
+
+    fn increment_on(self, current: &Version, part: Part, (year, month): (u64, u64)) -> Version {
+        match (self, part) {
+            (Self::Semver, Part::Major) => Version::new(current.major + 1, 0, 0),
+            (Self::Semver, Part::Minor) => Version::new(current.major, current.minor + 1, 0),
+            (_, Part::Patch) => Version::new(current.major, current.minor, current.patch + 1),
+            (Self::Calver, Part::Major | Part::Minor) => {
+                if (current.major, current.minor) < (year, month) {
+                    Version::new(year, month, 0)
+                } else {
+                    Version::new(current.major, current.minor, current.patch + 1)
+                }
+            }
+        }
+    }
+}
+
+/// Today's year and month (UTC).
+fn current_month() -> (u64, u64) {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400);
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calver() -> Result<()> {
+        let calver = VersionScheme::Calver;
+        assert_eq!(calver.parse("2024.03.0")?, Version::new(2024, 3, 0));
+        assert_eq!(calver.parse("2024.12.7")?, Version::new(2024, 12, 7));
+        for invalid in ["2024.13.0", "24.3.0", "2024.3", "2024.3.0-rc.1", "latest"] {
+            assert!(calver.parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(VersionScheme::Semver.parse("2024.03.0").is_err());
+
+        let current = Version::new(2024, 3, 2);
+        let march = (2024, 3);
+        let may = (2024, 5);
+        assert_eq!(
+            calver.increment_on(&current, Part::Patch, may),
+            Version::new(2024, 3, 3)
+        );
+        assert_eq!(
+            calver.increment_on(&current, Part::Minor, may),
+            Version::new(2024, 5, 0)
+        );
+        assert_eq!(
+            calver.increment_on(&current, Part::Major, march),
+            Version::new(2024, 3, 3)
+        );
+
+        let (year, month) = current_month();
+        assert!(year >= 2024 && (1..=12).contains(&month));
+
+        Ok(())
+    }
+}
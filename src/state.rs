@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the state is kept, relative to the workspace root.
+const STATE_FILE: &str = ".meta/state.json";
+
+/// Repositories that already succeeded in an interrupted operation, keyed by operation
+/// name (`push`, `commit`, ...), so `--continue` can skip them.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunState {
+    operations: BTreeMap<String, BTreeSet<PathBuf>>,
+}
+
+impl RunState {
+    /// Read the state of the workspace at `root`; a missing file is an empty state.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(STATE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {:?}; delete it to start over", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    /// Write the state to `root`, removing the file (and an empty `.meta/`) once nothing
+    /// is left to resume.
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = root.join(STATE_FILE);
+        if self.operations.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+            // Drop .meta/ too unless something else lives there
+            if let Some(dir) = path.parent()
+                && fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none())
+            {
+                fs::remove_dir(dir).with_context(|| format!("Failed to remove {:?}", dir))?;
+            }
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    pub fn is_done(&self, operation: &str, repo: &Path) -> bool {
+        self.operations
+            .get(operation)
+            .is_some_and(|done| done.contains(repo))
+    }
+
+    pub fn mark_done(&mut self, operation: &str, repo: &Path) {
+        self.operations
+            .entry(operation.to_string())
+            .or_default()
+            .insert(repo.to_path_buf());
+    }
+
+    /// Forget the progress of `operation`, leaving other operations alone.
+    pub fn clear(&mut self, operation: &str) {
+        self.operations.remove(operation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_state() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path();
+        assert_eq!(RunState::load(root)?, RunState::default());
+
+        let mut state = RunState::default();
+        state.mark_done("push", Path::new("/work/repo-a"));
+        state.mark_done("commit", Path::new("/work/repo-b"));
+        state.save(root)?;
+
+        let mut state = RunState::load(root)?;
+        assert!(state.is_done("push", Path::new("/work/repo-a")));
+        assert!(!state.is_done("push", Path::new("/work/repo-b")));
+        assert!(!state.is_done("commit", Path::new("/work/repo-a")));
+
+        state.clear("push");
+        state.save(root)?;
+        assert!(!RunState::load(root)?.is_done("push", Path::new("/work/repo-a")));
+        state.clear("commit");
+        state.save(root)?;
+        assert!(!root.join(STATE_FILE).exists());
+        assert!(!root.join(".meta").exists());
+
+        Ok(())
+    }
+}